@@ -31,4 +31,14 @@ mod test {
         let ast = super::get_nano_wasm_spectec_ast();
         assert!(!ast.is_empty());
     }
+
+    #[test]
+    fn test_pretty_print_reproduces_ast() {
+        let items = sexpr_parse::parse_sexpr_stream(super::NANO_WASM_AST_STR).unwrap();
+        let printed = sexpr_parse::PrettyOptions::default().format_stream(&items);
+        assert!(
+            printed == super::NANO_WASM_AST_STR,
+            "pretty-printed AST differs from the original"
+        );
+    }
 }
@@ -0,0 +1,136 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::sexpr::SExprItem;
+use crate::stream::StreamParser;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+
+/// Parses top-level items one at a time from a tokio `AsyncBufRead`, such as a socket or a request
+/// body. This is the async equivalent of `SExprStreamReader`, buffering the same bytes and
+/// returning the same items and errors.
+///
+/// `next_item` is cancel safe: bytes read before the future is dropped are kept for the next call.
+pub struct AsyncSExprStreamReader<R> {
+    inner: R,
+    parser: StreamParser,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncSExprStreamReader<R> {
+    #[must_use]
+    pub fn new(inner: R) -> Self {
+        Self::with_options(inner, ParserOptions::default())
+    }
+
+    #[must_use]
+    pub fn with_options(inner: R, options: ParserOptions) -> Self {
+        Self {
+            inner,
+            parser: StreamParser::new(options),
+        }
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the next top-level item, or `None` once the stream has ended or an error has been
+    /// returned.
+    pub async fn next_item(&mut self) -> Option<Result<SExprItem, SExprError>> {
+        while !self.parser.is_ready() {
+            let used = self.parser.feed(self.inner.fill_buf().await);
+            self.inner.consume(used);
+        }
+        self.parser.next_item()
+    }
+}
+
+/// Parses top-level items one at a time from a tokio `AsyncRead`, buffering it. See
+/// `AsyncSExprStreamReader`.
+pub fn parse_sexpr_async_reader<R: AsyncRead + Unpin>(
+    reader: R,
+) -> AsyncSExprStreamReader<BufReader<R>> {
+    AsyncSExprStreamReader::new(BufReader::new(reader))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use tokio::io::{AsyncWriteExt, BufReader};
+
+    async fn collect<R: tokio::io::AsyncBufRead + Unpin>(
+        mut reader: AsyncSExprStreamReader<R>,
+    ) -> Vec<Result<SExprItem, SExprError>> {
+        let mut items = Vec::new();
+        while let Some(item) = reader.next_item().await {
+            items.push(item);
+        }
+        items
+    }
+
+    /// Reads through a three-byte buffer, like the blocking stream tests.
+    async fn stream(input: &str, options: ParserOptions) -> Vec<Result<SExprItem, SExprError>> {
+        let reader = BufReader::with_capacity(3, input.as_bytes());
+        collect(AsyncSExprStreamReader::with_options(reader, options)).await
+    }
+
+    #[tokio::test]
+    async fn test_async_stream_matches_blocking_stream() {
+        let inputs = [
+            "(typ \"m\" (inst (alias nat)))\nfoo \"b\\\"a)r\"\n(rel \"R\" (a (b) c))\n",
+            "(a) (b)(c)",
+            "(a) (b",
+            "(a) (b))",
+            "(a) b\"c\"",
+            "(a) ) (b)",
+        ];
+        for input in inputs {
+            let expected = parse_sexpr_reader(input.as_bytes())
+                .map(|item| item.map_err(|err| err.to_string()))
+                .collect::<Vec<_>>();
+            let streamed = stream(input, ParserOptions::default())
+                .await
+                .into_iter()
+                .map(|item| item.map_err(|err| err.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(streamed, expected, "{input:?}");
+        }
+
+        let quoted = ParserOptions::new().with_quoted_atoms(true);
+        for input in ["|a b|c", "|a|(b)"] {
+            let expected = quoted.parse(input).unwrap_err();
+            let streamed = stream(input, quoted).await;
+            let err = match streamed.last() {
+                Some(Err(err)) => err,
+                other => panic!("expected error for {input:?}, got {other:?}"),
+            };
+            assert_eq!(err.to_string(), expected.to_string(), "{input:?}");
+        }
+
+        let options = ParserOptions::new().with_comments(true).with_lists(true);
+        let input = "; (a\n(b #| ) |# c) #| x |# (() d)";
+        let streamed = stream(input, options)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(streamed, options.parse(input).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_async_stream_yields_items_as_they_arrive() {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let mut reader = parse_sexpr_async_reader(reader);
+        writer.write_all(b"(a \"b\") (c").await.unwrap();
+        assert_eq!(
+            reader.next_item().await.unwrap().unwrap().to_string(),
+            "(a \"b\")"
+        );
+        writer.write_all(b" d)").await.unwrap();
+        drop(writer);
+        assert_eq!(
+            reader.next_item().await.unwrap().unwrap().to_string(),
+            "(c d)"
+        );
+        assert!(reader.next_item().await.is_none());
+    }
+}
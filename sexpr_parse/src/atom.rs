@@ -0,0 +1,129 @@
+/// What an atom represents, for atoms that are literals rather than plain symbols.
+///
+/// Returned by `classify_atom` and `AsSExpr::atom_kind`, so that consumers share one set of rules
+/// for recognising numbers and booleans. `SExprRef` and `InternedItem` store the kind of each atom
+/// when it is parsed, but `SExprItem` does not.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AtomKind {
+    /// Decimal digits with an optional sign, such as `42` or `-1`.
+    Int(i128),
+    /// `0x` followed by hexadecimal digits in either case, such as `0xFD`.
+    Hex(u128),
+    /// `true` or `false`.
+    Bool(bool),
+    /// Decimal digits with a fractional part, an exponent or both, such as `1.5` or `-2e10`.
+    Decimal(f64),
+    /// Anything else, including numbers too large to represent.
+    Symbol,
+}
+
+impl AtomKind {
+    /// Returns the value of an integer or hex atom, or `None` for any other kind of atom.
+    #[must_use]
+    pub fn as_integer(&self) -> Option<i128> {
+        match *self {
+            AtomKind::Int(n) => Some(n),
+            AtomKind::Hex(n) => i128::try_from(n).ok(),
+            _ => None,
+        }
+    }
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether the unsigned part of an atom is a decimal with a fraction or exponent.
+fn is_decimal(s: &str) -> bool {
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (s, None),
+    };
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((whole, fraction)) => is_digits(whole) && is_digits(fraction),
+        None => exponent.is_some() && is_digits(mantissa),
+    };
+    let exponent_ok = exponent
+        .is_none_or(|exponent| is_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)));
+    mantissa_ok && exponent_ok
+}
+
+/// Classifies an atom as a literal or a symbol.
+#[must_use]
+pub fn classify_atom(atom: &str) -> AtomKind {
+    match atom {
+        "true" => return AtomKind::Bool(true),
+        "false" => return AtomKind::Bool(false),
+        _ => {}
+    }
+    if let Some(hex) = atom.strip_prefix("0x") {
+        if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return u128::from_str_radix(hex, 16).map_or(AtomKind::Symbol, AtomKind::Hex);
+        }
+        return AtomKind::Symbol;
+    }
+    let unsigned = atom.strip_prefix(['+', '-']).unwrap_or(atom);
+    if is_digits(unsigned) {
+        atom.parse().map_or(AtomKind::Symbol, AtomKind::Int)
+    } else if is_decimal(unsigned) {
+        atom.parse().map_or(AtomKind::Symbol, AtomKind::Decimal)
+    } else {
+        AtomKind::Symbol
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test_classify_atom() {
+        for (atom, kind) in [
+            ("0", AtomKind::Int(0)),
+            ("42", AtomKind::Int(42)),
+            ("+7", AtomKind::Int(7)),
+            (
+                "-18446744073709551616",
+                AtomKind::Int(-18_446_744_073_709_551_616),
+            ),
+            ("0xFD", AtomKind::Hex(0xFD)),
+            ("0x0b", AtomKind::Hex(0x0B)),
+            ("true", AtomKind::Bool(true)),
+            ("false", AtomKind::Bool(false)),
+            ("1.5", AtomKind::Decimal(1.5)),
+            ("-2e3", AtomKind::Decimal(-2000.0)),
+            ("6.25E-2", AtomKind::Decimal(0.0625)),
+            ("nat", AtomKind::Symbol),
+            ("True", AtomKind::Symbol),
+            ("0x", AtomKind::Symbol),
+            ("0xg", AtomKind::Symbol),
+            ("-0x1", AtomKind::Symbol),
+            ("1.", AtomKind::Symbol),
+            (".5", AtomKind::Symbol),
+            ("1e", AtomKind::Symbol),
+            ("inf", AtomKind::Symbol),
+            ("-", AtomKind::Symbol),
+            ("1_000", AtomKind::Symbol),
+            (
+                "999999999999999999999999999999999999999999",
+                AtomKind::Symbol,
+            ),
+        ] {
+            assert_eq!(classify_atom(atom), kind, "{atom}");
+        }
+    }
+    #[test]
+    fn test_borrowed_atoms_are_classified_when_parsed() {
+        let items = parse_sexpr_stream_borrowed("(num 0x10) true nat").unwrap();
+        assert_eq!(
+            items,
+            [
+                SExprRef::Node("num", vec![SExprRef::Atom("0x10", AtomKind::Hex(16))]),
+                SExprRef::Atom("true", AtomKind::Bool(true)),
+                SExprRef::Atom("nat", AtomKind::Symbol),
+            ]
+        );
+        assert_eq!(items[2].atom_kind(), Some(AtomKind::Symbol));
+        assert_eq!(items[0].atom_kind(), None);
+    }
+}
@@ -0,0 +1,449 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::parse::{Builder, ItemBuilder, RefBuilder, check_depth};
+use crate::sexpr::{AsSExpr, SExprItem, SExprRef, SExprView};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+const MAGIC: &[u8] = b"SEXB";
+const VERSION: u8 = 1;
+
+const TAG_ATOM: u8 = 0;
+const TAG_TEXT: u8 = 1;
+const TAG_NODE: u8 = 2;
+const TAG_LIST: u8 = 3;
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        // Truncation keeps the low 7 bits, which are all that is written
+        #[allow(clippy::cast_possible_truncation)]
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    out.push(value as u8);
+}
+
+/// Assigns each distinct string an index, in the order they are first seen.
+#[derive(Default)]
+struct Dictionary<'s> {
+    indices: HashMap<&'s str, usize>,
+    strings: Vec<&'s str>,
+}
+
+impl<'s> Dictionary<'s> {
+    fn index(&mut self, s: &'s str) -> usize {
+        *self.indices.entry(s).or_insert_with(|| {
+            self.strings.push(s);
+            self.strings.len().saturating_sub(1)
+        })
+    }
+}
+
+/// Encodes the items and everything nested within them.
+///
+/// The items still to be encoded at each level are kept on an explicit stack rather than by
+/// recursion, so that deeply nested items can't overflow the call stack.
+fn encode_items<'s, S: AsSExpr>(
+    items: &'s [S],
+    dictionary: &mut Dictionary<'s>,
+    out: &mut Vec<u8>,
+) {
+    let mut levels = vec![items.iter()];
+    while let Some(siblings) = levels.last_mut() {
+        let Some(item) = siblings.next() else {
+            levels.pop();
+            continue;
+        };
+        match item.view() {
+            SExprView::Atom(atom) => {
+                out.push(TAG_ATOM);
+                write_varint(out, dictionary.index(atom));
+            }
+            SExprView::Text(text) => {
+                out.push(TAG_TEXT);
+                write_varint(out, dictionary.index(text));
+            }
+            SExprView::Node(name, items) => {
+                out.push(TAG_NODE);
+                write_varint(out, dictionary.index(name));
+                write_varint(out, items.len());
+                levels.push(items.iter());
+            }
+            SExprView::List(items) => {
+                out.push(TAG_LIST);
+                write_varint(out, items.len());
+                levels.push(items.iter());
+            }
+        }
+    }
+}
+
+/// Encodes a stream of items in a compact binary format, which `decode_sexpr_binary` reads back as
+/// exactly the same items without having to scan and unescape text.
+///
+/// The encoding starts with the magic bytes `SEXB` and a version byte, followed by a dictionary of
+/// every distinct atom, text and node name, and then the items, which refer to strings by their
+/// index in the dictionary. All numbers are unsigned LEB128 varints:
+///
+/// ```text
+/// stream := "SEXB" 0x01 count string* count item*
+/// string := length utf8-bytes
+/// item   := 0x00 index                 atom
+///         | 0x01 index                 text
+///         | 0x02 index count item*     node, with the index of its name
+///         | 0x03 count item*           list
+/// ```
+#[must_use]
+pub fn encode_sexpr_binary<S: AsSExpr>(items: &[S]) -> Vec<u8> {
+    let mut dictionary = Dictionary::default();
+    let mut encoded_items = Vec::new();
+    write_varint(&mut encoded_items, items.len());
+    encode_items(items, &mut dictionary, &mut encoded_items);
+
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    write_varint(&mut out, dictionary.strings.len());
+    for s in &dictionary.strings {
+        write_varint(&mut out, s.len());
+        out.extend_from_slice(s.as_bytes());
+    }
+    out.extend_from_slice(&encoded_items);
+    out
+}
+
+/// Reads the binary format, keeping track of the position for errors.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    strings: Vec<&'a str>,
+    max_depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, reason: &'static str) -> SExprError {
+        SExprError::InvalidBinary {
+            reason,
+            position: self.position,
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, SExprError> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.position = self.position.saturating_add(1);
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], SExprError> {
+        let bytes = self
+            .position
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.position = self.position.saturating_add(len);
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<usize, SExprError> {
+        let start = self.position;
+        let mut value: usize = 0;
+        let mut shift: u32 = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = usize::from(byte & 0x7f);
+            let shifted = bits.checked_shl(shift).filter(|v| v >> shift == bits);
+            let Some(shifted) = shifted else {
+                return Err(SExprError::InvalidBinary {
+                    reason: "number too large",
+                    position: start,
+                });
+            };
+            value |= shifted;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift = shift.saturating_add(7);
+        }
+    }
+
+    /// Reads a count of items that follow, each of which takes at least `min_size` bytes, so that
+    /// a corrupt count can't cause a huge allocation.
+    fn count(&mut self, min_size: usize) -> Result<usize, SExprError> {
+        let count = self.varint()?;
+        let remaining = self.bytes.len().saturating_sub(self.position);
+        if count > remaining.checked_div(min_size).unwrap_or(remaining) {
+            return Err(self.error("count exceeds the remaining input"));
+        }
+        Ok(count)
+    }
+
+    fn header(&mut self) -> Result<(), SExprError> {
+        if self.bytes(MAGIC.len()).ok() != Some(MAGIC) {
+            self.position = 0;
+            return Err(self.error("not an S-expression binary stream"));
+        }
+        if self.byte()? != VERSION {
+            return Err(self.error("unsupported version"));
+        }
+        let count = self.count(1)?;
+        self.strings.reserve(count);
+        for _ in 0..count {
+            let len = self.varint()?;
+            let start = self.position;
+            let bytes = self.bytes(len)?;
+            let s = std::str::from_utf8(bytes).map_err(|_| SExprError::InvalidBinary {
+                reason: "string is not valid UTF-8",
+                position: start,
+            })?;
+            self.strings.push(s);
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<&'a str, SExprError> {
+        let start = self.position;
+        let index = self.varint()?;
+        self.strings
+            .get(index)
+            .copied()
+            .ok_or(SExprError::InvalidBinary {
+                reason: "string index out of range",
+                position: start,
+            })
+    }
+
+    /// Reads `count` items and everything nested within them.
+    ///
+    /// Like the text parser, nodes that are still open are kept on an explicit stack rather than
+    /// by recursion.
+    fn items<B: Builder<'a>>(
+        &mut self,
+        builder: &mut B,
+        count: usize,
+    ) -> Result<Vec<B::Output>, SExprError> {
+        let mut node = OpenItem {
+            name: None,
+            start: self.position,
+            remaining: count,
+            items: Vec::with_capacity(count),
+        };
+        let mut parents = Vec::new();
+        loop {
+            if node.remaining == 0 {
+                let Some(parent) = parents.pop() else {
+                    return Ok(node.items);
+                };
+                let closed = std::mem::replace(&mut node, parent);
+                let item = match closed.name {
+                    Some(name) => builder.node(name, closed.items, closed.start, self.position),
+                    None => builder.list(closed.items, closed.start, self.position),
+                };
+                node.items.push(item);
+                continue;
+            }
+            node.remaining = node.remaining.saturating_sub(1);
+            let start = self.position;
+            match self.byte()? {
+                TAG_ATOM => {
+                    let atom = self.string()?;
+                    node.items.push(builder.atom(atom, start, self.position));
+                }
+                TAG_TEXT => {
+                    let text = self.string()?;
+                    node.items
+                        .push(builder.text(Cow::Borrowed(text), start, self.position));
+                }
+                tag @ (TAG_NODE | TAG_LIST) => {
+                    // The stream itself is at the bottom of the stack, so a top-level node is
+                    // nested one deep
+                    check_depth(self.max_depth, parents.len().saturating_add(1), start)?;
+                    let name = if tag == TAG_NODE {
+                        Some(self.string()?)
+                    } else {
+                        None
+                    };
+                    let count = self.count(2)?;
+                    let child = OpenItem {
+                        name,
+                        start,
+                        remaining: count,
+                        items: Vec::with_capacity(count),
+                    };
+                    parents.push(std::mem::replace(&mut node, child));
+                }
+                _ => {
+                    self.position = start;
+                    return Err(self.error("unknown item tag"));
+                }
+            }
+        }
+    }
+}
+
+/// A node or list whose items are still being decoded. Lists have no name.
+struct OpenItem<'a, T> {
+    name: Option<&'a str>,
+    start: usize,
+    remaining: usize,
+    items: Vec<T>,
+}
+
+/// Decodes the binary format with the given builder. Positions passed to the builder are byte
+/// offsets within the binary input.
+pub(crate) fn decode_with_builder<'a, B: Builder<'a>>(
+    bytes: &'a [u8],
+    options: ParserOptions,
+    mut builder: B,
+) -> Result<Vec<B::Output>, SExprError> {
+    let mut decoder = Decoder {
+        bytes,
+        position: 0,
+        strings: Vec::new(),
+        max_depth: options.max_depth(),
+    };
+    decoder.header()?;
+    let count = decoder.count(2)?;
+    let items = decoder.items(&mut builder, count)?;
+    if decoder.position != bytes.len() {
+        return Err(decoder.error("trailing bytes after the last item"));
+    }
+    Ok(items)
+}
+
+/// Decodes a stream of items encoded by `encode_sexpr_binary`.
+pub fn decode_sexpr_binary(bytes: &[u8]) -> Result<Vec<SExprItem>, SExprError> {
+    decode_sexpr_binary_with_options(bytes, &ParserOptions::default())
+}
+
+/// Decodes a stream of items encoded by `encode_sexpr_binary`, borrowing every string from the
+/// input rather than copying it.
+pub fn decode_sexpr_binary_borrowed(bytes: &[u8]) -> Result<Vec<SExprRef<'_>>, SExprError> {
+    decode_sexpr_binary_borrowed_with_options(bytes, &ParserOptions::default())
+}
+
+/// Decodes binary input with the given options, as `decode_sexpr_binary` does with the defaults.
+/// Only the maximum depth applies to binary input.
+pub fn decode_sexpr_binary_with_options(
+    bytes: &[u8],
+    options: &ParserOptions,
+) -> Result<Vec<SExprItem>, SExprError> {
+    decode_with_builder(bytes, *options, ItemBuilder)
+}
+
+/// Decodes binary input with the given options, as `decode_sexpr_binary_borrowed` does with the
+/// defaults.
+pub fn decode_sexpr_binary_borrowed_with_options<'a>(
+    bytes: &'a [u8],
+    options: &ParserOptions,
+) -> Result<Vec<SExprRef<'a>>, SExprError> {
+    decode_with_builder(bytes, *options, RefBuilder)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    const INPUT: &str = r#"
+(typ "instr" (inst (variant (case "nop" (mixop)) (case "block" nat))))
+(rel "Step" nat "escaped \"text\"\n" 0xFF)
+atom
+(() ((a) "b"))
+"#;
+
+    fn parse(input: &str) -> Vec<SExprItem> {
+        ParserOptions::new().with_lists(true).parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let items = parse(INPUT);
+        let encoded = encode_sexpr_binary(&items);
+        assert_eq!(decode_sexpr_binary(&encoded).unwrap(), items);
+        let borrowed = decode_sexpr_binary_borrowed(&encoded).unwrap();
+        assert_eq!(
+            borrowed
+                .iter()
+                .map(SExprRef::to_owned_item)
+                .collect::<Vec<_>>(),
+            items
+        );
+        // Each repeated string is only stored once
+        assert_eq!(
+            encoded.windows(3).filter(|window| window == b"nat").count(),
+            1
+        );
+        assert_eq!(
+            encode_sexpr_binary(
+                &ParserOptions::new()
+                    .with_lists(true)
+                    .parse_borrowed(INPUT)
+                    .unwrap()
+            ),
+            encoded
+        );
+        let empty: [SExprItem; 0] = [];
+        assert!(
+            decode_sexpr_binary(&encode_sexpr_binary(&empty))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_binary_rejects_malformed_input() {
+        let encoded = encode_sexpr_binary(&parse(INPUT));
+        let reason = |bytes: &[u8]| match decode_sexpr_binary(bytes) {
+            Err(SExprError::InvalidBinary { reason, .. }) => reason,
+            other => panic!("expected an invalid binary error, got {other:?}"),
+        };
+        assert_eq!(
+            reason(INPUT.as_bytes()),
+            "not an S-expression binary stream"
+        );
+        assert_eq!(reason(b"SEXB\x02"), "unsupported version");
+        for len in 0..encoded.len() {
+            reason(&encoded[..len]);
+        }
+        assert_eq!(
+            reason(&[&encoded[..], b"\0"].concat()),
+            "trailing bytes after the last item"
+        );
+        assert_eq!(
+            reason(b"SEXB\x01\x00\x01\x00\x00"),
+            "string index out of range"
+        );
+        assert_eq!(
+            reason(b"SEXB\x01\x01\x01\xff\x01\x00\x00"),
+            "string is not valid UTF-8"
+        );
+        assert_eq!(reason(b"SEXB\x01\x00\x01\x07\x00"), "unknown item tag");
+        assert_eq!(
+            reason(b"SEXB\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01"),
+            "number too large"
+        );
+        assert_eq!(
+            reason(b"SEXB\x01\x00\xff\xff\x03"),
+            "count exceeds the remaining input"
+        );
+    }
+
+    #[test]
+    fn test_binary_depth_limit() {
+        let deep = format!("{}{}", "(a ".repeat(300), ")".repeat(300));
+        let items = parse_sexpr_stream(&deep).unwrap();
+        let encoded = encode_sexpr_binary(&items);
+        // The 257th node follows a nine byte header and 256 nodes of three bytes each
+        let options = ParserOptions::default().with_max_depth(256);
+        assert!(matches!(
+            decode_sexpr_binary_with_options(&encoded, &options),
+            Err(SExprError::DepthLimitExceeded {
+                max_depth: 256,
+                position: 777
+            })
+        ));
+        assert_eq!(decode_sexpr_binary(&encoded).unwrap(), items);
+    }
+}
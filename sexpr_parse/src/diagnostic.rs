@@ -0,0 +1,206 @@
+use crate::error::SExprError;
+use crate::span::LineIndex;
+use std::fmt::Display;
+
+/// Renders an `SExprError` against the source text it was produced from, as `file:line:col`
+/// followed by the offending line with a caret under the error position.
+///
+/// Created by `SExprError::diagnostic`.
+pub struct Diagnostic<'a> {
+    error: &'a SExprError,
+    input: &'a str,
+    file: &'a str,
+}
+
+impl SExprError {
+    /// Returns the byte position of the innermost error, looking through any added context.
+    /// Errors that don't come from parsing text input have no position.
+    #[must_use]
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            SExprError::Io { position, .. }
+            | SExprError::Utf8 { position, .. }
+            | SExprError::InvalidUtf8 { position, .. }
+            | SExprError::UnexpectedByte { position, .. }
+            | SExprError::ExpectedSymbol { position }
+            | SExprError::MissingSeparator { position, .. }
+            | SExprError::InvalidEscape { position }
+            | SExprError::DepthLimitExceeded { position, .. } => Some(*position),
+            SExprError::InvalidAtom { .. }
+            | SExprError::InvalidList { .. }
+            | SExprError::InvalidBinary { .. } => None,
+            SExprError::WithContext { source, .. } => source.position(),
+        }
+    }
+
+    /// Prepares the error for display against the input that was given to `parse_sexpr_stream`.
+    /// `file` is only used as a label in the output.
+    #[must_use]
+    pub fn diagnostic<'a>(&'a self, input: &'a str, file: &'a str) -> Diagnostic<'a> {
+        Diagnostic {
+            error: self,
+            input,
+            file,
+        }
+    }
+
+    /// Describes the innermost error without its position.
+    fn message(&self) -> String {
+        match self {
+            SExprError::Io { source, .. } => format!("io error: {source}"),
+            SExprError::Utf8 { source, .. } => format!("utf8 error: {source}"),
+            SExprError::InvalidUtf8 { source, .. } => format!("utf8 error: {source}"),
+            SExprError::UnexpectedByte {
+                unexpected,
+                expected,
+                ..
+            } => format!(
+                "unexpected byte: found {}, expected {}",
+                describe_byte(*unexpected),
+                describe_byte(*expected)
+            ),
+            SExprError::ExpectedSymbol { .. } => "expected symbol".to_string(),
+            SExprError::MissingSeparator { unexpected, .. } => {
+                format!("missing separator before {}", describe_byte(*unexpected))
+            }
+            SExprError::InvalidEscape { .. } => "invalid escape sequence".to_string(),
+            SExprError::DepthLimitExceeded { max_depth, .. } => {
+                format!("nesting deeper than {max_depth}")
+            }
+            SExprError::InvalidAtom { atom } => {
+                format!("atom {atom:?} cannot be written as an S-expression")
+            }
+            SExprError::InvalidList { atom } => {
+                format!("list starting with atom {atom:?} cannot be written as an S-expression")
+            }
+            SExprError::InvalidBinary { reason, position } => {
+                format!("invalid binary encoding at byte {position}: {reason}")
+            }
+            SExprError::WithContext { source, .. } => source.message(),
+        }
+    }
+
+    /// Lists the context added to the error, from outermost to innermost.
+    fn contexts(&self) -> Vec<&str> {
+        let mut contexts = Vec::new();
+        let mut error = self;
+        while let SExprError::WithContext { source, context } = error {
+            contexts.push(context.as_str());
+            error = source;
+        }
+        contexts
+    }
+}
+
+fn describe_byte(byte: u8) -> String {
+    if byte.is_ascii_graphic() {
+        format!("'{}' ({byte:#x})", char::from(byte))
+    } else {
+        format!("{byte:#x}")
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error: {}", self.error.message())?;
+        let Some(position) = self.error.position() else {
+            for context in self.error.contexts() {
+                write!(f, "\n = note: while {context}")?;
+            }
+            return Ok(());
+        };
+        let lines = LineIndex::new(self.input);
+        let position = lines.position(position);
+        let line_number = position.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{gutter}--> {}:{}:{}",
+            self.file, position.line, position.column
+        )?;
+        writeln!(f, "{gutter} |")?;
+        let line = lines.line(position.line).unwrap_or_default();
+        // Tabs are copied so the caret lines up however wide the terminal draws them
+        let padding = line
+            .chars()
+            .take(position.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        writeln!(f, "{line_number} | {line}")?;
+        write!(f, "{gutter} | {padding}^")?;
+        for context in self.error.contexts() {
+            write!(f, "\n{gutter} = note: while {context}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{SExprError, parse_sexpr_stream};
+
+    #[test]
+    fn test_diagnostic_points_at_error() {
+        let input = "(typ \"m\"\n  (inst (alias nat)))\n(typ\"n\")\n";
+        let err = parse_sexpr_stream(input).unwrap_err();
+        assert_eq!(
+            err.diagnostic(input, "test.spectec-ast").to_string(),
+            [
+                "error: missing separator before '\"' (0x22)",
+                " --> test.spectec-ast:3:5",
+                "  |",
+                "3 | (typ\"n\")",
+                "  |     ^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_caret_keeps_tabs() {
+        let input = "(a\n\t(b\t\"é\"\t\"c\"d))\n";
+        let err = parse_sexpr_stream(input).unwrap_err();
+        assert_eq!(
+            err.diagnostic(input, "tabs").to_string(),
+            [
+                "error: missing separator before 'd' (0x64)",
+                " --> tabs:2:12",
+                "  |",
+                "2 | \t(b\t\"é\"\t\"c\"d))",
+                "  | \t  \t   \t   ^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_includes_context() {
+        let input = "(a\n  (b \"unterminated))\n";
+        let err = parse_sexpr_stream(input).unwrap_err();
+        let rendered = err.diagnostic(input, "x").to_string();
+        assert!(rendered.starts_with("error: io error: "), "{rendered}");
+        assert!(rendered.contains("--> x:3:1"), "{rendered}");
+
+        let input = "x (a (b c))";
+        let err = SExprError::UnexpectedByte {
+            unexpected: b'x',
+            expected: b'(',
+            position: 0,
+        }
+        .with_context("parsing beginning of new node");
+        assert_eq!(
+            err.diagnostic(input, "y").to_string(),
+            [
+                "error: unexpected byte: found 'x' (0x78), expected '(' (0x28)",
+                " --> y:1:1",
+                "  |",
+                "1 | x (a (b c))",
+                "  | ^",
+                "  = note: while parsing beginning of new node",
+            ]
+            .join("\n")
+        );
+    }
+}
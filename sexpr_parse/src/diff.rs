@@ -0,0 +1,615 @@
+use crate::pretty::PrettyOptions;
+use crate::sexpr::{AsSExpr, SExprView};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// One difference between two forests of S-expressions.
+///
+/// Paths are as returned by `Selector::select`: the index of the top-level item, followed by the
+/// index of each child within its parent node. Paths of removed items are into the old forest,
+/// and paths of inserted items into the new one.
+#[derive(Debug)]
+pub enum SExprDiff<'a, S> {
+    Inserted {
+        path: Vec<usize>,
+        item: &'a S,
+    },
+    Removed {
+        path: Vec<usize>,
+        item: &'a S,
+    },
+    /// Nodes that correspond to each other, but whose contents differ as described by `changes`.
+    Changed {
+        old_path: Vec<usize>,
+        new_path: Vec<usize>,
+        old: &'a S,
+        new: &'a S,
+        changes: Vec<SExprDiff<'a, S>>,
+    },
+}
+
+/// The differences between two forests of S-expressions, as found by `diff_forests`.
+///
+/// `Display` writes a report with a line per difference, nested under the nodes containing it.
+/// Inserted and removed items are written out in full.
+#[derive(Debug)]
+pub struct ForestDiff<'a, S> {
+    pub changes: Vec<SExprDiff<'a, S>>,
+}
+
+impl<S> ForestDiff<'_, S> {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Whether two items are identical, whatever their representation.
+fn same<A: AsSExpr, B: AsSExpr>(a: &A, b: &B) -> bool {
+    // Lists of children still to compare are kept on a stack rather than by recursing, so that
+    // deeply nested items can't overflow the call stack
+    let mut pending = vec![(std::slice::from_ref(a), std::slice::from_ref(b))];
+    while let Some((a_items, b_items)) = pending.pop() {
+        if a_items.len() != b_items.len() {
+            return false;
+        }
+        for (a, b) in a_items.iter().zip(b_items) {
+            match (a.view(), b.view()) {
+                (SExprView::Atom(a), SExprView::Atom(b))
+                | (SExprView::Text(a), SExprView::Text(b)) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (SExprView::Node(a, a_items), SExprView::Node(b, b_items)) => {
+                    if a != b {
+                        return false;
+                    }
+                    pending.push((a_items, b_items));
+                }
+                (SExprView::List(a_items), SExprView::List(b_items)) => {
+                    pending.push((a_items, b_items));
+                }
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Identifies a node across versions by its name, along with its first item if that is text,
+/// as with `(typ "instr" ...)` or `(case "nop" ...)`.
+fn node_key<S: AsSExpr>(item: &S) -> Option<(&str, Option<&str>)> {
+    match item.view() {
+        SExprView::Node(name, items) => {
+            let text = match items.first().map(AsSExpr::view) {
+                Some(SExprView::Text(text)) => Some(text),
+                _ => None,
+            };
+            Some((name, text))
+        }
+        SExprView::Atom(_) | SExprView::Text(_) | SExprView::List(_) => None,
+    }
+}
+
+/// Whether the items are the same entity, so that any differences should be reported within them
+/// rather than as one being replaced by the other. Lists have no key, so are only the same entity
+/// when identical.
+fn corresponds<S: AsSExpr>(a: &S, b: &S) -> bool {
+    match (node_key(a), node_key(b)) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => same(a, b),
+        _ => false,
+    }
+}
+
+fn child_path(path: &[usize], index: usize) -> Vec<usize> {
+    let mut path = path.to_vec();
+    path.push(index);
+    path
+}
+
+/// The largest table `align_table` fills in. Longer runs of siblings are lined up by position.
+const MAX_TABLE_CELLS: usize = 1 << 20;
+
+/// How an item in one list of siblings lines up with the other list, by indices into the lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Corresponding items, which may still differ within.
+    Pair(usize, usize),
+    Removed(usize),
+    Inserted(usize),
+}
+
+/// Lines up two lists of sibling items, pairing up corresponding items in the order they appear,
+/// so that as few items as possible are reported inserted or removed.
+///
+/// Nodes whose key appears exactly once in each list are paired first, keeping the longest run of
+/// them that is in the same order in both. Only the items between those pairs go through the
+/// table in `align_table`, so that reordering a long list doesn't cost time quadratic in its
+/// length.
+fn align<S: AsSExpr>(old: &[S], new: &[S]) -> Vec<Step> {
+    // Unchanged runs at either end are common, and don't need to be lined up any further
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b)| same(*a, *b))
+        .count();
+    let (old_rest, new_rest) = (
+        old.get(prefix..).unwrap_or_default(),
+        new.get(prefix..).unwrap_or_default(),
+    );
+    let suffix = old_rest
+        .iter()
+        .rev()
+        .zip(new_rest.iter().rev())
+        .take_while(|(a, b)| same(*a, *b))
+        .count();
+    let old_end = old.len().saturating_sub(suffix);
+    let new_end = new.len().saturating_sub(suffix);
+
+    let mut steps: Vec<Step> = (0..prefix).map(|i| Step::Pair(i, i)).collect();
+    let (mut i, mut j) = (prefix, prefix);
+    for (anchor_i, anchor_j) in unique_anchors(old, new, prefix, old_end, new_end) {
+        align_table(old, new, (i, anchor_i), (j, anchor_j), &mut steps);
+        steps.push(Step::Pair(anchor_i, anchor_j));
+        i = anchor_i.saturating_add(1);
+        j = anchor_j.saturating_add(1);
+    }
+    align_table(old, new, (i, old_end), (j, new_end), &mut steps);
+    steps.extend(
+        (0..suffix).map(|k| Step::Pair(old_end.saturating_add(k), new_end.saturating_add(k))),
+    );
+    steps
+}
+
+/// Finds the nodes in `old[start..old_end]` and `new[start..new_end]` whose key appears exactly
+/// once in each, and returns the indices of the longest sequence of them in the same order in
+/// both.
+fn unique_anchors<S: AsSExpr>(
+    old: &[S],
+    new: &[S],
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+) -> Vec<(usize, usize)> {
+    // For each key, how often it appears in each list and where it last appeared
+    let mut keys: HashMap<_, (usize, usize, usize, usize)> = HashMap::new();
+    for (i, item) in old.iter().enumerate().take(old_end).skip(start) {
+        if let Some(key) = node_key(item) {
+            let entry = keys.entry(key).or_default();
+            entry.0 = entry.0.saturating_add(1);
+            entry.2 = i;
+        }
+    }
+    for (j, item) in new.iter().enumerate().take(new_end).skip(start) {
+        if let Some(key) = node_key(item) {
+            let entry = keys.entry(key).or_default();
+            entry.1 = entry.1.saturating_add(1);
+            entry.3 = j;
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = keys
+        .into_values()
+        .filter(|&(old_count, new_count, _, _)| old_count == 1 && new_count == 1)
+        .map(|(_, _, i, j)| (i, j))
+        .collect();
+    pairs.sort_unstable();
+
+    // Patience sorting: tails[k] is the index into pairs of the smallest new index ending an
+    // increasing run of length k + 1, and previous links each pair to the one before it in its run
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+    for (n, &(_, j)) in pairs.iter().enumerate() {
+        let k = tails.partition_point(|&t| pairs.get(t).is_some_and(|&(_, tj)| tj < j));
+        previous.push(k.checked_sub(1).and_then(|k| tails.get(k).copied()));
+        match tails.get_mut(k) {
+            Some(tail) => *tail = n,
+            None => tails.push(n),
+        }
+    }
+    let mut anchors = Vec::with_capacity(tails.len());
+    let mut next = tails.last().copied();
+    while let Some(n) = next {
+        anchors.extend(pairs.get(n));
+        next = previous.get(n).copied().flatten();
+    }
+    anchors.reverse();
+    anchors
+}
+
+/// Lines up `old[old_start..old_end]` with `new[new_start..new_end]` by finding the longest
+/// sequence of corresponding items in both, and appends the steps to `steps`.
+///
+/// The table takes time and space proportional to the product of the lengths, so runs too long for
+/// `MAX_TABLE_CELLS` are instead lined up by position, pairing items that correspond and reporting
+/// the rest as removed and inserted.
+fn align_table<S: AsSExpr>(
+    old: &[S],
+    new: &[S],
+    (old_start, old_end): (usize, usize),
+    (new_start, new_end): (usize, usize),
+    steps: &mut Vec<Step>,
+) {
+    let old_mid = old.get(old_start..old_end).unwrap_or_default();
+    let new_mid = new.get(new_start..new_end).unwrap_or_default();
+    let width = new_mid.len().saturating_add(1);
+    let cells = old_mid.len().saturating_add(1).saturating_mul(width);
+    if cells > MAX_TABLE_CELLS {
+        for k in 0..old_mid.len().max(new_mid.len()) {
+            let (i, j) = (old_start.saturating_add(k), new_start.saturating_add(k));
+            match (old_mid.get(k), new_mid.get(k)) {
+                (Some(a), Some(b)) if corresponds(a, b) => steps.push(Step::Pair(i, j)),
+                (a, b) => {
+                    if a.is_some() {
+                        steps.push(Step::Removed(i));
+                    }
+                    if b.is_some() {
+                        steps.push(Step::Inserted(j));
+                    }
+                }
+            }
+        }
+        return;
+    }
+
+    // lengths[i * width + j] is the length of the longest sequence of corresponding items in
+    // old_mid[i..] and new_mid[j..]
+    let mut lengths = vec![0usize; cells];
+    let at = |i: usize, j: usize| i.saturating_mul(width).saturating_add(j);
+    for (i, a) in old_mid.iter().enumerate().rev() {
+        for (j, b) in new_mid.iter().enumerate().rev() {
+            let length = if corresponds(a, b) {
+                lengths
+                    .get(at(i.saturating_add(1), j.saturating_add(1)))
+                    .map_or(0, |l| l.saturating_add(1))
+            } else {
+                let down = lengths.get(at(i.saturating_add(1), j)).copied();
+                let right = lengths.get(at(i, j.saturating_add(1))).copied();
+                down.max(right).unwrap_or_default()
+            };
+            if let Some(slot) = lengths.get_mut(at(i, j)) {
+                *slot = length;
+            }
+        }
+    }
+
+    let length = |i: usize, j: usize| lengths.get(at(i, j)).copied().unwrap_or_default();
+    let (mut i, mut j) = (0, 0);
+    loop {
+        let old_index = old_start.saturating_add(i);
+        let new_index = new_start.saturating_add(j);
+        match (old_mid.get(i), new_mid.get(j)) {
+            (Some(a), Some(b))
+                if corresponds(a, b)
+                    && length(i, j)
+                        == length(i.saturating_add(1), j.saturating_add(1)).saturating_add(1) =>
+            {
+                steps.push(Step::Pair(old_index, new_index));
+                i = i.saturating_add(1);
+                j = j.saturating_add(1);
+            }
+            (Some(_), b)
+                if b.is_none()
+                    || length(i.saturating_add(1), j) >= length(i, j.saturating_add(1)) =>
+            {
+                steps.push(Step::Removed(old_index));
+                i = i.saturating_add(1);
+            }
+            (_, Some(_)) => {
+                steps.push(Step::Inserted(new_index));
+                j = j.saturating_add(1);
+            }
+            (_, None) => break,
+        }
+    }
+}
+
+/// Two lists of siblings being compared, within the nodes `parent` if they aren't the top level.
+struct Level<'a, S> {
+    old_path: Vec<usize>,
+    new_path: Vec<usize>,
+    old: &'a [S],
+    new: &'a [S],
+    parent: Option<(&'a S, &'a S)>,
+    steps: std::vec::IntoIter<Step>,
+    changes: Vec<SExprDiff<'a, S>>,
+}
+
+impl<'a, S: AsSExpr> Level<'a, S> {
+    fn new(
+        old_path: Vec<usize>,
+        new_path: Vec<usize>,
+        old: &'a [S],
+        new: &'a [S],
+        parent: Option<(&'a S, &'a S)>,
+    ) -> Self {
+        Self {
+            old_path,
+            new_path,
+            old,
+            new,
+            parent,
+            steps: align(old, new).into_iter(),
+            changes: Vec::new(),
+        }
+    }
+}
+
+/// Finds the differences between two lists of sibling items, descending into corresponding nodes
+/// that differ. Levels being compared are kept on a stack rather than by recursing, so that deeply
+/// nested items can't overflow the call stack.
+fn diff_items<'a, S: AsSExpr>(old: &'a [S], new: &'a [S]) -> Vec<SExprDiff<'a, S>> {
+    let mut levels = vec![Level::new(Vec::new(), Vec::new(), old, new, None)];
+    while let Some(level) = levels.last_mut() {
+        let nested = match level.steps.next() {
+            Some(Step::Pair(i, j)) => match (level.old.get(i), level.new.get(j)) {
+                (Some(a), Some(b)) => match (a.view(), b.view()) {
+                    (SExprView::Node(_, a_items), SExprView::Node(_, b_items)) if !same(a, b) => {
+                        Some(Level::new(
+                            child_path(&level.old_path, i),
+                            child_path(&level.new_path, j),
+                            a_items,
+                            b_items,
+                            Some((a, b)),
+                        ))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            Some(Step::Removed(i)) => {
+                if let Some(item) = level.old.get(i) {
+                    let path = child_path(&level.old_path, i);
+                    level.changes.push(SExprDiff::Removed { path, item });
+                }
+                None
+            }
+            Some(Step::Inserted(j)) => {
+                if let Some(item) = level.new.get(j) {
+                    let path = child_path(&level.new_path, j);
+                    level.changes.push(SExprDiff::Inserted { path, item });
+                }
+                None
+            }
+            None => {
+                let Some(done) = levels.pop() else { break };
+                match (done.parent, levels.last_mut()) {
+                    (Some((old, new)), Some(level)) => level.changes.push(SExprDiff::Changed {
+                        old_path: done.old_path,
+                        new_path: done.new_path,
+                        old,
+                        new,
+                        changes: done.changes,
+                    }),
+                    _ => return done.changes,
+                }
+                None
+            }
+        };
+        levels.extend(nested);
+    }
+    Vec::new()
+}
+
+/// Compares two forests of S-expressions structurally, ignoring layout.
+///
+/// Nodes in the two forests correspond if they have the same name and the same leading text,
+/// if any, so a changed `(typ "instr" ...)` is reported as changes within it rather than as one
+/// definition being removed and another inserted. Atoms and text correspond only when equal.
+#[must_use]
+pub fn diff_forests<'a, S: AsSExpr>(old: &'a [S], new: &'a [S]) -> ForestDiff<'a, S> {
+    ForestDiff {
+        changes: diff_items(old, new),
+    }
+}
+
+fn format_path(path: &[usize]) -> String {
+    path.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Summarises a changed node by its key, as `(name "text" …)`.
+fn write_heading<S: AsSExpr>(f: &mut std::fmt::Formatter<'_>, item: &S) -> std::fmt::Result {
+    match node_key(item) {
+        Some((name, Some(text))) => {
+            write!(f, "({name} ")?;
+            crate::write::write_text(f, text)?;
+            write!(f, " …)")
+        }
+        Some((name, None)) => write!(f, "({name} …)"),
+        None => crate::write::write_item(f, item),
+    }
+}
+
+/// Writes a line per change, with the changes within a changed node indented under it. Nested
+/// changes are kept on a stack rather than by recursing, as in `diff_items`.
+fn write_changes<S: AsSExpr>(
+    f: &mut std::fmt::Formatter<'_>,
+    changes: &[SExprDiff<'_, S>],
+) -> std::fmt::Result {
+    let mut levels = vec![changes.iter()];
+    while let Some(level) = levels.last_mut() {
+        let Some(change) = level.next() else {
+            levels.pop();
+            continue;
+        };
+        let pad = " ".repeat(levels.len().saturating_sub(1).saturating_mul(2));
+        let (sign, path, item) = match change {
+            SExprDiff::Inserted { path, item } => ('+', path, item),
+            SExprDiff::Removed { path, item } => ('-', path, item),
+            SExprDiff::Changed {
+                new_path,
+                new,
+                changes,
+                ..
+            } => {
+                write!(f, "{pad}~ {} ", format_path(new_path))?;
+                write_heading(f, *new)?;
+                writeln!(f)?;
+                levels.push(changes.iter());
+                continue;
+            }
+        };
+        let path = format_path(path);
+        // Continuation lines of the item line up under its first line
+        let continuation = " ".repeat(path.len().saturating_add(2));
+        let printed = PrettyOptions::default().format(*item);
+        for (n, line) in printed.lines().enumerate() {
+            if n == 0 {
+                writeln!(f, "{pad}{sign} {path} {line}")?;
+            } else {
+                writeln!(f, "{pad}{sign}{continuation}{line}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl<S: AsSExpr> Display for ForestDiff<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_changes(f, &self.changes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test_diff_ignores_layout() {
+        let old = parse_sexpr_stream("(typ \"a\" (inst (alias nat)))\n(rel \"r\" x)").unwrap();
+        let new =
+            parse_sexpr_stream("(typ \"a\"\n  (inst\n    (alias nat)\n  )\n)\n(rel \"r\" x)\n")
+                .unwrap();
+        assert!(diff_forests(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_report() {
+        let old = parse_sexpr_stream(
+            r#"
+(typ "instr" (inst (variant (case "nop" x) (case "drop" y))))
+(typ "val" (inst (alias nat)))
+(rel "Old" a)
+"#,
+        )
+        .unwrap();
+        let new = parse_sexpr_stream(
+            r#"
+(typ "instr" (inst (variant (case "nop" z) (case "select" y) (case "drop" y))))
+(rel "New" b)
+(typ "val" (inst (alias nat)))
+"#,
+        )
+        .unwrap();
+        let diff = diff_forests(&old, &new);
+        assert_eq!(
+            diff.to_string(),
+            [
+                "~ 0 (typ \"instr\" …)",
+                "  ~ 0.1 (inst …)",
+                "    ~ 0.1.0 (variant …)",
+                "      ~ 0.1.0.0 (case \"nop\" …)",
+                "        - 0.1.0.0.1 x",
+                "        + 0.1.0.0.1 z",
+                "      + 0.1.0.1 (case \"select\" y)",
+                "+ 1 (rel \"New\" b)",
+                "- 2 (rel \"Old\" a)",
+                "",
+            ]
+            .join("\n")
+        );
+        let SExprDiff::Changed {
+            old_path, new_path, ..
+        } = &diff.changes[0]
+        else {
+            panic!("expected a change: {diff:?}");
+        };
+        assert_eq!(
+            (old_path.as_slice(), new_path.as_slice()),
+            (&[0][..], &[0][..])
+        );
+    }
+
+    #[test]
+    fn test_diff_writes_inserted_items_in_full() {
+        let old = parse_sexpr_stream("(a)").unwrap();
+        let long = format!("(b{})", " (c \"long enough to wrap\")".repeat(4));
+        let new = parse_sexpr_stream(&format!("(a) {long}")).unwrap();
+        let report = diff_forests(&old, &new).to_string();
+        assert!(
+            report.starts_with("+ 1 (b\n+     (c \"long enough to wrap\")\n"),
+            "{report}"
+        );
+        assert!(report.ends_with("+   )\n"), "{report}");
+    }
+
+    #[test]
+    fn test_diff_reordered_siblings() {
+        let items: Vec<_> = (0..5000).map(|n| format!("(rel \"r{n}\" x)")).collect();
+        let old = parse_sexpr_stream(&items.join("\n")).unwrap();
+        let mut moved = items[1..].to_vec();
+        moved[2499] = "(rel \"r2500\" y)".to_owned();
+        moved.push(items[0].clone());
+        let new = parse_sexpr_stream(&moved.join("\n")).unwrap();
+        assert_eq!(
+            diff_forests(&old, &new).to_string(),
+            [
+                "- 0 (rel \"r0\" x)",
+                "~ 2499 (rel \"r2500\" …)",
+                "  - 2500.1 x",
+                "  + 2499.1 y",
+                "+ 4999 (rel \"r0\" x)",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_up_long_runs_by_position() {
+        let old: Vec<_> = (0..1500)
+            .map(|n| SExprItem::Atom(format!("a{n}")))
+            .collect();
+        let new: Vec<_> = (0..1500)
+            .map(|n| SExprItem::Atom(format!("{}{n}", if n % 2 == 0 { 'b' } else { 'a' })))
+            .collect();
+        let diff = diff_forests(&old, &new);
+        assert_eq!(diff.changes.len(), 1500);
+        assert!(matches!(
+            &diff.changes[..2],
+            [
+                SExprDiff::Removed { path: old_path, .. },
+                SExprDiff::Inserted { path: new_path, .. },
+            ] if old_path == &[0] && new_path == &[0]
+        ));
+    }
+
+    #[test]
+    fn test_diff_deeply_nested() {
+        let depth = 3000;
+        let nested = |atom: &str| format!("{}{atom}{}", "(a ".repeat(depth), ")".repeat(depth));
+        let old = parse_sexpr_stream(&nested("x")).unwrap();
+        let new = parse_sexpr_stream(&nested("y")).unwrap();
+        let diff = diff_forests(&old, &new);
+        let mut changes = &diff.changes;
+        for _ in 0..depth {
+            let [
+                SExprDiff::Changed {
+                    changes: within, ..
+                },
+            ] = &changes[..]
+            else {
+                panic!("expected one change");
+            };
+            changes = within;
+        }
+        assert!(matches!(
+            &changes[..],
+            [SExprDiff::Removed { .. }, SExprDiff::Inserted { .. }]
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
@@ -15,6 +16,13 @@ pub enum SExprError {
         source: FromUtf8Error,
         position: usize,
     },
+    /// Input that was checked to be UTF-8 in place, such as a memory-mapped file, wasn't.
+    #[error("utf8 error at {position}: {source}")]
+    InvalidUtf8 {
+        #[source]
+        source: Utf8Error,
+        position: usize,
+    },
     #[error("unexpected byte at {position}: found {unexpected:#x}, expected {expected:#x}")]
     UnexpectedByte {
         unexpected: u8,
@@ -25,6 +33,19 @@ pub enum SExprError {
     ExpectedSymbol { position: usize },
     #[error("missing separator before byte {unexpected:#x} at {position}")]
     MissingSeparator { unexpected: u8, position: usize },
+    #[error("invalid escape sequence at {position}")]
+    InvalidEscape { position: usize },
+    #[error("nesting deeper than {max_depth} at {position}")]
+    DepthLimitExceeded { max_depth: usize, position: usize },
+    #[error("atom {atom:?} cannot be written as an S-expression")]
+    InvalidAtom { atom: String },
+    #[error("list starting with atom {atom:?} cannot be written as an S-expression")]
+    InvalidList { atom: String },
+    #[error("invalid binary encoding at byte {position}: {reason}")]
+    InvalidBinary {
+        reason: &'static str,
+        position: usize,
+    },
     #[error("{context}: {source}")]
     WithContext {
         #[source]
@@ -33,6 +54,14 @@ pub enum SExprError {
     },
 }
 
+/// An error in the syntax of a `Selector`, at a byte position within the selector.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid selector at {position}: {message}")]
+pub struct SelectorError {
+    pub position: usize,
+    pub message: &'static str,
+}
+
 impl SExprError {
     #[must_use]
     pub fn with_context<R: AsRef<str>>(self, context: R) -> Self {
@@ -41,4 +70,63 @@ impl SExprError {
             context: context.as_ref().to_string(),
         }
     }
+
+    /// Shifts every position in the error by `offset`, for errors from parsing a slice of a larger
+    /// input.
+    #[must_use]
+    pub(crate) fn offset_by(self, offset: usize) -> Self {
+        match self {
+            SExprError::Io { source, position } => SExprError::Io {
+                source,
+                position: position.saturating_add(offset),
+            },
+            SExprError::Utf8 { source, position } => SExprError::Utf8 {
+                source,
+                position: position.saturating_add(offset),
+            },
+            SExprError::InvalidUtf8 { source, position } => SExprError::InvalidUtf8 {
+                source,
+                position: position.saturating_add(offset),
+            },
+            SExprError::UnexpectedByte {
+                unexpected,
+                expected,
+                position,
+            } => SExprError::UnexpectedByte {
+                unexpected,
+                expected,
+                position: position.saturating_add(offset),
+            },
+            SExprError::ExpectedSymbol { position } => SExprError::ExpectedSymbol {
+                position: position.saturating_add(offset),
+            },
+            SExprError::MissingSeparator {
+                unexpected,
+                position,
+            } => SExprError::MissingSeparator {
+                unexpected,
+                position: position.saturating_add(offset),
+            },
+            SExprError::InvalidEscape { position } => SExprError::InvalidEscape {
+                position: position.saturating_add(offset),
+            },
+            SExprError::DepthLimitExceeded {
+                max_depth,
+                position,
+            } => SExprError::DepthLimitExceeded {
+                max_depth,
+                position: position.saturating_add(offset),
+            },
+            SExprError::InvalidAtom { atom } => SExprError::InvalidAtom { atom },
+            SExprError::InvalidList { atom } => SExprError::InvalidList { atom },
+            // Positions within binary input are never part of a larger text input
+            SExprError::InvalidBinary { reason, position } => {
+                SExprError::InvalidBinary { reason, position }
+            }
+            SExprError::WithContext { source, context } => SExprError::WithContext {
+                source: Box::new(source.offset_by(offset)),
+                context,
+            },
+        }
+    }
 }
@@ -0,0 +1,253 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::parse::{
+    OpenNode, check_depth, ensure_item_boundary, open_node, read_symbol, read_text,
+};
+use crate::reader::Reader;
+use std::borrow::Cow;
+
+/// What the parser found at one point in the input.
+///
+/// Atoms and node names are borrowed from the input, as is text unless it contained escapes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SExprEventKind<'a> {
+    /// An opening parenthesis and the node name following it.
+    StartNode(&'a str),
+    /// The opening parenthesis of a list without a name, with `ParserOptions::with_lists`.
+    StartList,
+    Atom(&'a str),
+    Text(Cow<'a, str>),
+    /// The closing parenthesis of the most recently started node or list that is still open.
+    EndNode,
+}
+
+/// An event along with the byte range of the input it covers.
+///
+/// `StartNode` covers the opening parenthesis and the name, `StartList` the opening parenthesis,
+/// and `EndNode` the closing parenthesis. The offsets can be turned into line and column positions
+/// with `LineIndex`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SExprEvent<'a> {
+    pub kind: SExprEventKind<'a>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Reads S-expressions as a flat sequence of events, without building a tree.
+///
+/// Accepts exactly the input the tree parsers do and reports the same errors, though events
+/// before an error have already been returned by the time it's found. Iteration ends after the
+/// first error.
+pub struct SExprEvents<'a> {
+    reader: Reader<'a>,
+    /// How many nodes have been started but not yet ended.
+    depth: usize,
+    /// Whether an item has been read, so whatever follows must be separated from it.
+    after_item: bool,
+    done: bool,
+}
+
+impl<'a> SExprEvents<'a> {
+    #[must_use]
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    #[must_use]
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Self {
+            reader: Reader::with_options(input, options),
+            depth: 0,
+            after_item: false,
+            done: false,
+        }
+    }
+
+    /// How many nodes are open after the events returned so far.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn read_next(&mut self) -> Result<Option<SExprEvent<'a>>, SExprError> {
+        let reader = &mut self.reader;
+        if self.after_item {
+            ensure_item_boundary(reader)?;
+        }
+        reader.consume_whitespace().map_err(|err| SExprError::Io {
+            source: err,
+            position: reader.position(),
+        })?;
+        let start = reader.position();
+        let kind = match reader.peek() {
+            None if self.depth == 0 => return Ok(None),
+            None => {
+                return Err(SExprError::Io {
+                    source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
+                    position: start,
+                });
+            }
+            Some(b')') if self.depth == 0 => {
+                return Err(SExprError::UnexpectedByte {
+                    unexpected: b')',
+                    expected: b'(',
+                    position: start,
+                });
+            }
+            Some(b')') => {
+                reader.advance(1).map_err(|err| SExprError::Io {
+                    source: err,
+                    position: start,
+                })?;
+                self.depth = self.depth.saturating_sub(1);
+                SExprEventKind::EndNode
+            }
+            Some(b'(') => {
+                let max_depth = reader.options().max_depth();
+                check_depth(max_depth, self.depth.saturating_add(1), start)?;
+                let node: OpenNode<'a, ()> = open_node(reader)?;
+                self.depth = self.depth.saturating_add(1);
+                match node.name {
+                    Some(name) => SExprEventKind::StartNode(name),
+                    None => SExprEventKind::StartList,
+                }
+            }
+            Some(b'"') => SExprEventKind::Text(read_text(reader)?),
+            Some(_) => SExprEventKind::Atom(read_symbol(reader)?),
+        };
+        // Unlike a node name, the opening parenthesis of a list needs no separator after it
+        self.after_item = kind != SExprEventKind::StartList;
+        Ok(Some(SExprEvent {
+            kind,
+            start,
+            end: reader.position(),
+        }))
+    }
+}
+
+impl<'a> Iterator for SExprEvents<'a> {
+    type Item = Result<SExprEvent<'a>, SExprError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.read_next().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
+/// Reads the input as a sequence of events. See `SExprEvents`.
+#[must_use]
+pub fn parse_sexpr_events(input: &str) -> SExprEvents<'_> {
+    SExprEvents::new(input)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    /// Rebuilds trees from events, to check they describe exactly what the tree parser produces.
+    fn build(input: &str, options: ParserOptions) -> Result<Vec<SExprItem>, SExprError> {
+        let mut stack: Vec<(Option<String>, Vec<SExprItem>)> = Vec::new();
+        let mut items = Vec::new();
+        for event in options.events(input) {
+            let item = match event?.kind {
+                SExprEventKind::StartNode(name) => {
+                    stack.push((Some(name.to_owned()), Vec::new()));
+                    continue;
+                }
+                SExprEventKind::StartList => {
+                    stack.push((None, Vec::new()));
+                    continue;
+                }
+                SExprEventKind::Atom(atom) => SExprItem::Atom(atom.to_owned()),
+                SExprEventKind::Text(text) => SExprItem::Text(text.into_owned()),
+                SExprEventKind::EndNode => match stack.pop().unwrap() {
+                    (Some(name), children) => SExprItem::Node(name, children),
+                    (None, children) => SExprItem::List(children),
+                },
+            };
+            match stack.last_mut() {
+                Some((_, children)) => children.push(item),
+                None => items.push(item),
+            }
+        }
+        assert!(stack.is_empty());
+        Ok(items)
+    }
+
+    #[test]
+    fn test_events_match_tree_parser() {
+        let inputs = [
+            r#"(typ "m" (inst (alias nat))) (rel "Step" (exp "\"x\"" nat))"#,
+            "atom \"text\"\n(a (b (c)))\n",
+            "(a) (b)(c)",
+            "(a (b \"unterminated))",
+            "(a))",
+            ")",
+            "(a (b) c",
+            "(a\"b\")",
+            "( a)",
+        ];
+        for input in inputs {
+            let options = ParserOptions::new();
+            assert_eq!(
+                format!("{:?}", build(input, options)),
+                format!("{:?}", options.parse(input)),
+                "{input}"
+            );
+        }
+        let commented = "(a ; comment\n #| block |# b) ; end";
+        let options = ParserOptions::new().with_comments(true);
+        assert_eq!(
+            build(commented, options).unwrap(),
+            options.parse(commented).unwrap()
+        );
+        let dialect = "(() (a) ((b) \"c\") ( d e) |x y| (|z| ()))";
+        let options = ParserOptions::new()
+            .with_lists(true)
+            .with_quoted_atoms(true);
+        assert_eq!(
+            build(dialect, options).unwrap(),
+            options.parse(dialect).unwrap()
+        );
+        let deep = "(a ".repeat(10);
+        let options = ParserOptions::new().with_max_depth(4);
+        assert_eq!(
+            format!("{:?}", build(&deep, options)),
+            format!("{:?}", options.parse(&deep))
+        );
+    }
+
+    #[test]
+    fn test_events_have_positions() {
+        let input = "(rel \"Step\"\n  x)";
+        let events: Vec<_> = parse_sexpr_events(input)
+            .map(Result::unwrap)
+            .map(|event| (event.kind, &input[event.start..event.end]))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (SExprEventKind::StartNode("rel"), "(rel"),
+                (SExprEventKind::Text("Step".into()), "\"Step\""),
+                (SExprEventKind::Atom("x"), "x"),
+                (SExprEventKind::EndNode, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_count_without_building() {
+        let input = r#"(rel "A" (x)) (typ "t") (rec (rel "B") (rel "C"))"#;
+        let rels = parse_sexpr_events(input)
+            .map(Result::unwrap)
+            .filter(|event| event.kind == SExprEventKind::StartNode("rel"))
+            .count();
+        assert_eq!(rels, 3);
+    }
+}
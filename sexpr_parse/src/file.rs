@@ -0,0 +1,155 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::sexpr::SExprItem;
+use memmap2::Mmap;
+use std::path::{Path, PathBuf};
+
+/// A file mapped into memory and checked to be UTF-8, so that it can be parsed in place rather
+/// than read into a buffer first.
+///
+/// The mapping reflects the file as it is on disk, so see `open` for why the file must not be
+/// changed while it is open.
+pub struct MappedFile {
+    path: PathBuf,
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file into memory and checks that it is UTF-8.
+    ///
+    /// Errors don't include the path; `parse_sexpr_file` adds it as context.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other process, until the
+    /// `MappedFile` is dropped. Otherwise the contents seen through `as_str` may change or stop
+    /// being UTF-8, which is undefined behaviour, and reads past the new end of a truncated file
+    /// crash the process.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self, SExprError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(|source| SExprError::Io {
+            source,
+            position: 0,
+        })?;
+        // SAFETY: The mapping is only read, and the caller guarantees that the file isn't modified
+        // while it is open.
+        let map = unsafe { Mmap::map(&file) }.map_err(|source| SExprError::Io {
+            source,
+            position: 0,
+        })?;
+        std::str::from_utf8(&map).map_err(|source| SExprError::InvalidUtf8 {
+            source,
+            position: source.valid_up_to(),
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            map,
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: The contents were checked to be UTF-8 in `open`, and its caller guarantees that
+        // they don't change while the file is open.
+        unsafe { std::str::from_utf8_unchecked(&self.map) }
+    }
+}
+
+/// Adds the path of the file to an error from opening or parsing it.
+pub(crate) fn in_file(err: SExprError, path: &Path) -> SExprError {
+    err.with_context(path.display().to_string())
+}
+
+/// Parses the file at the given path like `parse_sexpr_stream`, mapping it into memory rather than
+/// reading it into a buffer. Errors have the path added as context.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated until this returns, as for `MappedFile::open`. Read
+/// the file into a string and use `parse_sexpr_stream` instead if that can't be ruled out.
+pub unsafe fn parse_sexpr_file<P: AsRef<Path>>(path: P) -> Result<Vec<SExprItem>, SExprError> {
+    // SAFETY: The caller upholds the same guarantee.
+    unsafe { parse_sexpr_file_with_options(path, &ParserOptions::default()) }
+}
+
+/// Parses the file at the given path with the given options, as `parse_sexpr_file` does with the
+/// defaults.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated until this returns, as for `MappedFile::open`.
+pub unsafe fn parse_sexpr_file_with_options<P: AsRef<Path>>(
+    path: P,
+    options: &ParserOptions,
+) -> Result<Vec<SExprItem>, SExprError> {
+    let path = path.as_ref();
+    // SAFETY: The caller guarantees that the file isn't changed until this returns, and the
+    // mapping is dropped before then.
+    let file = unsafe { MappedFile::open(path) }.map_err(|err| in_file(err, path))?;
+    options
+        .parse(file.as_str())
+        .map_err(|err| in_file(err, path))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::io::Write;
+
+    /// Writes a file that only this test uses, so it isn't changed while it is parsed.
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("sexpr_parse_{}_{name}", std::process::id()));
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(contents)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let input = "(typ \"m\" (inst (alias nat)))\n(rel \"r\")\n";
+        let path = temp_file("valid", input.as_bytes());
+        assert_eq!(
+            unsafe { parse_sexpr_file(&path) }.unwrap(),
+            parse_sexpr_stream(input).unwrap()
+        );
+        let empty = temp_file("empty", b"");
+        assert!(unsafe { parse_sexpr_file(&empty) }.unwrap().is_empty());
+        let commented = temp_file("commented", b"(a) ; (b)\n");
+        let options = ParserOptions::new().with_comments(true);
+        assert_eq!(
+            unsafe { parse_sexpr_file_with_options(&commented, &options) }.unwrap(),
+            parse_sexpr_stream("(a)").unwrap()
+        );
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(empty).unwrap();
+        std::fs::remove_file(commented).unwrap();
+    }
+
+    #[test]
+    fn test_parse_file_errors_carry_path() {
+        let malformed = temp_file("malformed", b"(a)\n(b");
+        let invalid_utf8 = temp_file("invalid_utf8", b"(a \"\xff\")");
+        let missing = std::env::temp_dir().join("sexpr_parse_missing_file");
+        for (path, message) in [
+            (&malformed, "io error at 6"),
+            (&invalid_utf8, "utf8 error at 4"),
+            (&missing, "io error at 0"),
+        ] {
+            let err = unsafe { parse_sexpr_file(path) }.unwrap_err();
+            let SExprError::WithContext { source, context } = &err else {
+                panic!("expected context: {err:?}");
+            };
+            assert_eq!(context, &path.display().to_string());
+            assert!(source.to_string().starts_with(message), "{err}");
+        }
+        std::fs::remove_file(malformed).unwrap();
+        std::fs::remove_file(invalid_utf8).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+use crate::atom::{AtomKind, classify_atom};
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::parse::Builder;
+use crate::sexpr::{AsSExpr, SExprView};
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A shared, immutable string from an `Interner`.
+///
+/// Cloning a symbol only bumps a reference count, and symbols from the same interner compare
+/// equal by pointer without looking at their contents. Each symbol also carries its
+/// classification as an atom, worked out once when the string was first interned.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>, AtomKind);
+
+impl Symbol {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> AtomKind {
+        self.1
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        // Symbols from different interners may still hold equal strings
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Stores one copy of each distinct string it is given, handing out `Symbol`s that share it.
+///
+/// An interner can be reused across several parses so that their symbols are shared too.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    symbols: HashMap<Arc<str>, AtomKind>,
+}
+
+impl Interner {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the symbol for the string, adding it to the interner if it isn't there yet.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(symbol) = self.get(s) {
+            return symbol;
+        }
+        let symbol: Arc<str> = Arc::from(s);
+        let kind = classify_atom(s);
+        self.symbols.insert(Arc::clone(&symbol), kind);
+        Symbol(symbol, kind)
+    }
+
+    /// Returns the symbol for the string if it has already been interned.
+    #[must_use]
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.symbols
+            .get_key_value(s)
+            .map(|(symbol, &kind)| Symbol(Arc::clone(symbol), kind))
+    }
+
+    /// The number of distinct strings interned.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+/// An S-expression item whose atoms and node names are interned `Symbol`s.
+///
+/// Text is rarely repeated, so it is stored as a plain `String`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InternedItem {
+    Atom(Symbol),
+    Text(String),
+    Node(Symbol, Vec<InternedItem>),
+    List(Vec<InternedItem>),
+}
+
+impl Display for InternedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::write::write_item(f, self)
+    }
+}
+
+impl AsSExpr for InternedItem {
+    fn view(&self) -> SExprView<'_, Self> {
+        match self {
+            InternedItem::Atom(s) => SExprView::Atom(s),
+            InternedItem::Text(s) => SExprView::Text(s),
+            InternedItem::Node(name, items) => SExprView::Node(name, items),
+            InternedItem::List(items) => SExprView::List(items),
+        }
+    }
+
+    fn atom_kind(&self) -> Option<AtomKind> {
+        match self {
+            InternedItem::Atom(s) => Some(s.kind()),
+            InternedItem::Text(_) | InternedItem::Node(..) | InternedItem::List(_) => None,
+        }
+    }
+}
+
+/// Builds `InternedItem`s, interning atoms and node names as they are read.
+pub(crate) struct InternBuilder<'i> {
+    pub(crate) interner: &'i mut Interner,
+}
+
+impl<'a> Builder<'a> for InternBuilder<'_> {
+    type Output = InternedItem;
+
+    fn atom(&mut self, atom: &'a str, _start: usize, _end: usize) -> InternedItem {
+        InternedItem::Atom(self.interner.intern(atom))
+    }
+
+    fn text(&mut self, text: Cow<'a, str>, _start: usize, _end: usize) -> InternedItem {
+        InternedItem::Text(text.into_owned())
+    }
+
+    fn node(
+        &mut self,
+        name: &'a str,
+        items: Vec<InternedItem>,
+        _start: usize,
+        _end: usize,
+    ) -> InternedItem {
+        InternedItem::Node(self.interner.intern(name), items)
+    }
+
+    fn list(&mut self, items: Vec<InternedItem>, _start: usize, _end: usize) -> InternedItem {
+        InternedItem::List(items)
+    }
+}
+
+/// Parses the input like `parse_sexpr_stream`, interning atoms and node names in the given
+/// interner.
+pub fn parse_sexpr_stream_interned(
+    input: &str,
+    interner: &mut Interner,
+) -> Result<Vec<InternedItem>, SExprError> {
+    ParserOptions::default().parse_interned(input, interner)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test_interned_symbols_are_shared() {
+        let input = r#"(typ "t" (var x) (var y)) (rel "r" (var x))"#;
+        let mut interner = Interner::new();
+        let items = parse_sexpr_stream_interned(input, &mut interner).unwrap();
+        // typ, rel, var, x, y
+        assert_eq!(interner.len(), 5);
+
+        let InternedItem::Node(_, typ) = &items[0] else {
+            panic!("expected node: {items:?}");
+        };
+        let InternedItem::Node(_, rel) = &items[1] else {
+            panic!("expected node: {items:?}");
+        };
+        let (InternedItem::Node(var1, _), InternedItem::Node(var2, x)) = (&typ[1], &rel[1]) else {
+            panic!("expected nodes: {typ:?} {rel:?}");
+        };
+        assert!(std::ptr::eq(var1.as_str(), var2.as_str()));
+        assert_eq!(x, &[InternedItem::Atom(interner.get("x").unwrap())]);
+        assert_eq!(x[0].atom_kind(), Some(AtomKind::Symbol));
+        assert_eq!(interner.intern("0x10").kind(), AtomKind::Hex(16));
+
+        // Interned items are written exactly like plain ones
+        let plain = parse_sexpr_stream(input).unwrap();
+        assert_eq!(
+            items.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            plain.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+    }
+}
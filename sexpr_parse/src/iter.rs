@@ -0,0 +1,118 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::parse::{Builder, ItemBuilder, ensure_item_boundary, read_item};
+use crate::reader::Reader;
+use crate::sexpr::SExprItem;
+
+/// Parses top-level items one at a time, stopping after the first error.
+pub(crate) struct BuiltItems<'a, B> {
+    reader: Reader<'a>,
+    builder: B,
+    /// Whether an item has been read, so the next one must be separated from it.
+    after_item: bool,
+    done: bool,
+}
+
+impl<'a, B: Builder<'a>> BuiltItems<'a, B> {
+    pub(crate) fn new(input: &'a str, options: ParserOptions, builder: B) -> Self {
+        Self {
+            reader: Reader::with_options(input, options),
+            builder,
+            after_item: false,
+            done: false,
+        }
+    }
+
+    fn read_next(&mut self) -> Result<Option<B::Output>, SExprError> {
+        if self.after_item {
+            ensure_item_boundary(&self.reader)?;
+        }
+        self.reader
+            .consume_whitespace()
+            .map_err(|err| SExprError::Io {
+                source: err,
+                position: self.reader.position(),
+            })?;
+        if self.reader.is_eof() {
+            return Ok(None);
+        }
+        let item = read_item(&mut self.reader, &mut self.builder)?;
+        self.after_item = true;
+        Ok(Some(item))
+    }
+}
+
+impl<'a, B: Builder<'a>> Iterator for BuiltItems<'a, B> {
+    type Item = Result<B::Output, SExprError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.read_next().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
+/// Lazily parses top-level items from a string.
+///
+/// Each call to `next` parses just enough of the input to return the next item, so iteration can
+/// be abandoned early without parsing the rest. Iteration ends after the first error.
+pub struct SExprIter<'a>(BuiltItems<'a, ItemBuilder>);
+
+impl<'a> SExprIter<'a> {
+    #[must_use]
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    #[must_use]
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Self(BuiltItems::new(input, options, ItemBuilder))
+    }
+}
+
+impl Iterator for SExprIter<'_> {
+    type Item = Result<SExprItem, SExprError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Lazily parses top-level items from a string. See `SExprIter`.
+#[must_use]
+pub fn parse_sexpr_iter(input: &str) -> SExprIter<'_> {
+    SExprIter::new(input)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test_iter_is_lazy() {
+        // Everything after the first item is malformed, but never needs to be parsed
+        let input = r#"(typ "m" (inst (alias nat))) (rel"#;
+        let first = parse_sexpr_iter(input).next().unwrap().unwrap();
+        assert_eq!(
+            first,
+            parse_sexpr_stream(r#"(typ "m" (inst (alias nat)))"#).unwrap()[0]
+        );
+    }
+
+    #[test]
+    fn test_iter_stops_after_first_error() {
+        let mut iter = parse_sexpr_iter("(a) (b)(c) (d)");
+        assert!(matches!(iter.next(), Some(Ok(_))));
+        assert!(matches!(iter.next(), Some(Ok(_))));
+        assert!(matches!(
+            iter.next(),
+            Some(Err(SExprError::MissingSeparator { position: 7, .. }))
+        ));
+        assert!(iter.next().is_none());
+    }
+}
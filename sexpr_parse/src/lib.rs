@@ -8,11 +8,58 @@
 #![cfg_attr(test, allow(clippy::unwrap_used, clippy::expect_used, clippy::panic))]
 #![allow(clippy::doc_markdown, clippy::missing_errors_doc)]
 
+#[cfg(feature = "async")]
+mod async_stream;
+mod atom;
+mod binary;
+mod diagnostic;
+mod diff;
 mod error;
+mod events;
+mod file;
+mod intern;
+mod iter;
+mod options;
+mod parallel;
 mod parse;
+mod pretty;
 mod reader;
+mod recover;
+mod scan;
+mod select;
 mod sexpr;
+mod span;
+mod stream;
+mod visit;
+mod write;
+mod zipper;
 
-pub use error::SExprError;
-pub use parse::parse_sexpr_stream;
-pub use sexpr::SExprItem;
+#[cfg(feature = "async")]
+pub use async_stream::{AsyncSExprStreamReader, parse_sexpr_async_reader};
+pub use atom::{AtomKind, classify_atom};
+pub use binary::{
+    decode_sexpr_binary, decode_sexpr_binary_borrowed, decode_sexpr_binary_borrowed_with_options,
+    decode_sexpr_binary_with_options, encode_sexpr_binary,
+};
+pub use diagnostic::Diagnostic;
+pub use diff::{ForestDiff, SExprDiff, diff_forests};
+pub use error::{SExprError, SelectorError};
+pub use events::{SExprEvent, SExprEventKind, SExprEvents, parse_sexpr_events};
+pub use file::{MappedFile, parse_sexpr_file, parse_sexpr_file_with_options};
+pub use intern::{InternedItem, Interner, Symbol, parse_sexpr_stream_interned};
+pub use iter::{SExprIter, parse_sexpr_iter};
+pub use options::ParserOptions;
+pub use parallel::{
+    parse_sexpr_stream_parallel, parse_sexpr_stream_parallel_map,
+    parse_sexpr_stream_parallel_with_options,
+};
+pub use parse::{parse_sexpr_stream, parse_sexpr_stream_borrowed, parse_sexpr_stream_spanned};
+pub use pretty::{ClosingParen, PrettyOptions};
+pub use recover::parse_sexpr_stream_recovering;
+pub use select::{Selected, Selector, select};
+pub use sexpr::{AsSExpr, SExprItem, SExprRef, SExprView};
+pub use span::{LineIndex, Position, Span, SpanTree, Spanned};
+pub use stream::{SExprStreamReader, parse_sexpr_reader};
+pub use visit::{SExprFold, SExprVisitor, SExprVisitorMut, fold, visit, visit_mut};
+pub use write::{is_valid_atom, to_sexpr_string};
+pub use zipper::SExprZipper;
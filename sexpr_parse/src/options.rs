@@ -0,0 +1,149 @@
+use crate::error::SExprError;
+use crate::events::SExprEvents;
+use crate::intern::{InternBuilder, InternedItem, Interner};
+use crate::iter::SExprIter;
+use crate::parse::{ItemBuilder, RefBuilder, SpannedBuilder, parse_with_builder};
+use crate::recover::parse_recovering;
+use crate::sexpr::{SExprItem, SExprRef};
+use crate::span::{LineIndex, Spanned};
+
+/// Configures how S-expressions are parsed.
+///
+/// The default options accept exactly the S-expressions produced by SpecTec. Extensions are
+/// opt-in through the `with_*` methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParserOptions {
+    comments: bool,
+    lists: bool,
+    quoted_atoms: bool,
+    max_depth: usize,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            comments: false,
+            lists: false,
+            quoted_atoms: false,
+            max_depth: usize::MAX,
+        }
+    }
+}
+
+impl ParserOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treats line comments (`;` up to the end of the line) and block comments (`#|` up to the
+    /// matching `|#`, which may nest) as whitespace.
+    #[must_use]
+    pub fn with_comments(mut self, enabled: bool) -> Self {
+        self.comments = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn comments(&self) -> bool {
+        self.comments
+    }
+
+    /// Accepts lists without a name, parsed as `SExprItem::List`: the empty list `()`, and lists
+    /// whose first item is text or another list, such as `((a) "b")`. A list starting with an
+    /// atom is still a node named by that atom, which may now be preceded by whitespace.
+    #[must_use]
+    pub fn with_lists(mut self, enabled: bool) -> Self {
+        self.lists = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn lists(&self) -> bool {
+        self.lists
+    }
+
+    /// Accepts atoms and node names quoted in vertical bars, such as `|two words|`, which may
+    /// contain any character but `|`. The bars are kept as part of the atom, so that it is
+    /// written back the same way.
+    #[must_use]
+    pub fn with_quoted_atoms(mut self, enabled: bool) -> Self {
+        self.quoted_atoms = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn quoted_atoms(&self) -> bool {
+        self.quoted_atoms
+    }
+
+    /// Sets how deeply nodes may be nested before parsing fails with
+    /// `SExprError::DepthLimitExceeded`, counting a top-level node as depth 1.
+    ///
+    /// Unlimited by default. Parsing never recurses, but dropping, printing and decoding items
+    /// does, so set a limit when parsing untrusted input. The Wasm specification nests to a depth
+    /// of well under 256.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Parses the input with these options, as `parse_sexpr_stream` does with the defaults.
+    pub fn parse(&self, input: &str) -> Result<Vec<SExprItem>, SExprError> {
+        parse_with_builder(input, *self, ItemBuilder)
+    }
+
+    /// Parses the input with these options, as `parse_sexpr_stream_borrowed` does with the
+    /// defaults.
+    pub fn parse_borrowed<'a>(&self, input: &'a str) -> Result<Vec<SExprRef<'a>>, SExprError> {
+        parse_with_builder(input, *self, RefBuilder)
+    }
+
+    /// Parses the input with these options, as `parse_sexpr_stream_interned` does with the
+    /// defaults.
+    pub fn parse_interned(
+        &self,
+        input: &str,
+        interner: &mut Interner,
+    ) -> Result<Vec<InternedItem>, SExprError> {
+        parse_with_builder(input, *self, InternBuilder { interner })
+    }
+
+    /// Parses the input with these options, as `parse_sexpr_stream_recovering` does with the
+    /// defaults.
+    #[must_use]
+    pub fn parse_recovering(&self, input: &str) -> (Vec<SExprItem>, Vec<SExprError>) {
+        parse_recovering(input, *self)
+    }
+
+    /// Reads the input as events with these options, as `parse_sexpr_events` does with the
+    /// defaults.
+    #[must_use]
+    pub fn events<'a>(&self, input: &'a str) -> SExprEvents<'a> {
+        SExprEvents::with_options(input, *self)
+    }
+
+    /// Lazily parses the input with these options, as `parse_sexpr_iter` does with the defaults.
+    #[must_use]
+    pub fn iter<'a>(&self, input: &'a str) -> SExprIter<'a> {
+        SExprIter::with_options(input, *self)
+    }
+
+    /// Parses the input with these options, as `parse_sexpr_stream_spanned` does with the
+    /// defaults.
+    pub fn parse_spanned(&self, input: &str) -> Result<Vec<Spanned<SExprItem>>, SExprError> {
+        parse_with_builder(
+            input,
+            *self,
+            SpannedBuilder {
+                lines: LineIndex::new(input),
+            },
+        )
+    }
+}
@@ -0,0 +1,219 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::parse::{Builder, ItemBuilder, RefBuilder, parse_with_builder};
+use crate::scan::{Boundary, TopLevelScanner};
+use crate::sexpr::{SExprItem, SExprRef};
+use std::num::NonZeroUsize;
+
+/// How many chunks each thread is given, so that threads which finish early aren't left idle
+/// while another works through a chunk of unusually large items.
+const CHUNKS_PER_THREAD: usize = 4;
+
+pub(crate) fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Splits the input into about `pieces` chunks of whole top-level items, each paired with its
+/// offset in the input.
+///
+/// Chunks are only cut where whitespace follows an item, so parsing each chunk on its own accepts
+/// and rejects exactly what parsing the whole input would. Malformed input that the scanner
+/// can't find the end of is left in one chunk for the parser to report on.
+fn split_chunks(input: &str, options: ParserOptions, pieces: usize) -> Vec<(usize, &str)> {
+    let target = input.len().checked_div(pieces).unwrap_or(input.len());
+    let bytes = input.as_bytes();
+    let mut scanner = TopLevelScanner::new(options);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        let end = match scanner.scan(b) {
+            Boundary::None => None,
+            Boundary::After => i.checked_add(1),
+            // The byte is scanned again below, as the start of whatever follows the item
+            Boundary::Before => Some(i),
+        };
+        if let Some(end) = end
+            && end.saturating_sub(start) >= target
+            && bytes.get(end).is_some_and(u8::is_ascii_whitespace)
+            && let Some(chunk) = input.get(start..end)
+        {
+            chunks.push((start, chunk));
+            start = end;
+        }
+        if end != Some(i) {
+            i = i.saturating_add(1);
+        }
+    }
+    if let Some(rest) = input.get(start..)
+        && (!rest.is_empty() || chunks.is_empty())
+    {
+        chunks.push((start, rest));
+    }
+    chunks
+}
+
+/// Parses chunks of the input on up to `threads` threads, passing the items of each chunk to `f`
+/// on the thread that parsed them.
+///
+/// The results are in input order. If any chunk fails to parse, the error from the earliest one
+/// is returned, which is the error parsing the whole input in one go would have stopped at.
+pub(crate) fn parse_chunks<'a, B, T, F>(
+    input: &'a str,
+    options: ParserOptions,
+    builder: &B,
+    f: &F,
+    threads: usize,
+) -> Result<Vec<T>, SExprError>
+where
+    B: Builder<'a> + Clone + Sync,
+    T: Send,
+    F: Fn(Vec<B::Output>) -> T + Sync,
+{
+    let threads = threads.max(1);
+    let chunks = split_chunks(input, options, threads.saturating_mul(CHUNKS_PER_THREAD));
+    let parse_chunk = |&(offset, chunk): &(usize, &'a str)| {
+        parse_with_builder(chunk, options, builder.clone())
+            .map(f)
+            .map_err(|err| err.offset_by(offset))
+    };
+    if threads == 1 || chunks.len() <= 1 {
+        return chunks.iter().map(parse_chunk).collect();
+    }
+
+    let mut results: Vec<Option<Result<T, SExprError>>> =
+        std::iter::repeat_with(|| None).take(chunks.len()).collect();
+    std::thread::scope(|scope| {
+        // Chunks are dealt out in turn, so each thread gets a similar share of the input
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                let chunks = &chunks;
+                let parse_chunk = &parse_chunk;
+                scope.spawn(move || {
+                    chunks
+                        .iter()
+                        .enumerate()
+                        .skip(worker)
+                        .step_by(threads)
+                        .map(|(index, chunk)| (index, parse_chunk(chunk)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for worker in workers {
+            let parsed = worker
+                .join()
+                .unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+            for (index, result) in parsed {
+                if let Some(slot) = results.get_mut(index) {
+                    *slot = Some(result);
+                }
+            }
+        }
+    });
+    results.into_iter().flatten().collect()
+}
+
+/// Parses the input like `parse_sexpr_stream`, splitting it into chunks of whole top-level items
+/// that are parsed on separate threads.
+///
+/// The result is identical to `parse_sexpr_stream`, including which error is returned when the
+/// input is malformed.
+pub fn parse_sexpr_stream_parallel(input: &str) -> Result<Vec<SExprItem>, SExprError> {
+    parse_sexpr_stream_parallel_with_options(input, &ParserOptions::default())
+}
+
+/// Parses the input with the given options, as `parse_sexpr_stream_parallel` does with the
+/// defaults.
+pub fn parse_sexpr_stream_parallel_with_options(
+    input: &str,
+    options: &ParserOptions,
+) -> Result<Vec<SExprItem>, SExprError> {
+    let chunks = parse_chunks(
+        input,
+        *options,
+        &ItemBuilder,
+        &|items| items,
+        available_threads(),
+    )?;
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+/// Parses the input in chunks of whole top-level items across several threads, as
+/// `parse_sexpr_stream_parallel_with_options` does, and passes the items of each chunk to `f` on
+/// the thread that parsed them. Returns the result of `f` for each chunk, in input order.
+///
+/// This lets further processing of the items, such as decoding them, run in parallel too.
+/// How the input is divided into chunks is unspecified.
+pub fn parse_sexpr_stream_parallel_map<'a, T, F>(
+    input: &'a str,
+    options: &ParserOptions,
+    f: F,
+) -> Result<Vec<T>, SExprError>
+where
+    T: Send,
+    F: Fn(Vec<SExprRef<'a>>) -> T + Sync,
+{
+    parse_chunks(input, *options, &RefBuilder, &f, available_threads())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::ItemBuilder;
+    use crate::parse_sexpr_stream;
+
+    fn parse_in_threads(input: &str, threads: usize) -> Result<Vec<SExprItem>, SExprError> {
+        parse_chunks(
+            input,
+            ParserOptions::default(),
+            &ItemBuilder,
+            &|items| items,
+            threads,
+        )
+        .map(|chunks| chunks.into_iter().flatten().collect())
+    }
+
+    #[test]
+    fn test_split_chunks_at_whitespace_between_items() {
+        let input = "(a (b)) atom \"text\"\n(c)(d) (e\n";
+        let chunks = split_chunks(input, ParserOptions::default(), 100);
+        assert_eq!(
+            chunks,
+            [
+                (0, "(a (b))"),
+                (7, " atom"),
+                (12, " \"text\""),
+                (19, "\n(c)(d)"),
+                (26, " (e\n"),
+            ]
+        );
+        assert_eq!(
+            split_chunks(input, ParserOptions::default(), 1),
+            [(0, input)]
+        );
+        assert_eq!(split_chunks("", ParserOptions::default(), 4), [(0, "")]);
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let inputs = [
+            "(typ \"m\" (inst (alias nat)))\n(typ \"n\" (inst (alias nat)))\n(rel \"r\")\n",
+            "(a) (b) (c)(d) (e) (f)",
+            "(a) (b) (c) \"unterminated (d) (e)",
+            "(a) (b) (c) (d) ) (e)",
+            "(a) (b) (c) (d \"bad \\q\") (e) (f (g",
+            "",
+            "   ",
+        ];
+        for input in inputs {
+            for threads in [1, 2, 3, 8] {
+                assert_eq!(
+                    format!("{:?}", parse_in_threads(input, threads)),
+                    format!("{:?}", parse_sexpr_stream(input)),
+                    "{input:?} with {threads} threads"
+                );
+            }
+        }
+    }
+}
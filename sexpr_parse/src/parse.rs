@@ -1,52 +1,189 @@
+use crate::atom::classify_atom;
 use crate::error::SExprError;
+use crate::iter::BuiltItems;
+use crate::options::ParserOptions;
 use crate::reader::Reader;
-use crate::sexpr::SExprItem;
-use std::io::Read;
+use crate::sexpr::{SExprItem, SExprRef};
+use crate::span::{LineIndex, SpanTree, Spanned};
+use std::borrow::Cow;
 
-fn read_one_byte(r: &mut Reader) -> Result<u8, SExprError> {
-    let mut byte = [0u8; 1];
-    let n = r.read(&mut byte).map_err(|e| SExprError::Io {
-        source: e,
-        position: r.position(),
-    })?;
-    if n == 0 {
-        Err(SExprError::Io {
-            source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
-            position: r.position(),
-        })
-    } else {
-        Ok(byte[0])
+/// Constructs output items as the parser recognises them, given the byte range each one covers.
+///
+/// Atoms and node names are borrowed from the input, as is text unless it contained escapes.
+pub(crate) trait Builder<'a> {
+    type Output;
+
+    fn atom(&mut self, atom: &'a str, start: usize, end: usize) -> Self::Output;
+    fn text(&mut self, text: Cow<'a, str>, start: usize, end: usize) -> Self::Output;
+    fn node(
+        &mut self,
+        name: &'a str,
+        items: Vec<Self::Output>,
+        start: usize,
+        end: usize,
+    ) -> Self::Output;
+    fn list(&mut self, items: Vec<Self::Output>, start: usize, end: usize) -> Self::Output;
+}
+
+/// Builds plain `SExprItem`s, discarding positions.
+#[derive(Clone, Copy)]
+pub(crate) struct ItemBuilder;
+
+impl<'a> Builder<'a> for ItemBuilder {
+    type Output = SExprItem;
+
+    fn atom(&mut self, atom: &'a str, _start: usize, _end: usize) -> SExprItem {
+        SExprItem::Atom(atom.to_owned())
+    }
+
+    fn text(&mut self, text: Cow<'a, str>, _start: usize, _end: usize) -> SExprItem {
+        SExprItem::Text(text.into_owned())
+    }
+
+    fn node(
+        &mut self,
+        name: &'a str,
+        items: Vec<SExprItem>,
+        _start: usize,
+        _end: usize,
+    ) -> SExprItem {
+        SExprItem::Node(name.to_owned(), items)
+    }
+
+    fn list(&mut self, items: Vec<SExprItem>, _start: usize, _end: usize) -> SExprItem {
+        SExprItem::List(items)
     }
 }
 
-fn read_symbol(r: &mut Reader) -> Result<String, SExprError> {
-    let mut buf = Vec::new();
-    loop {
-        if let Some(c) = r.peek()
-            && !c.is_ascii_whitespace()
-            && c != b'('
-            && c != b')'
-            && c != b'"'
-        {
-            buf.push(c);
-            r.advance(1).map_err(|e| SExprError::Io {
-                source: e,
-                position: r.position(),
-            })?;
-        } else {
-            break;
+/// Builds `SExprRef`s borrowing from the input, discarding positions.
+#[derive(Clone, Copy)]
+pub(crate) struct RefBuilder;
+
+impl<'a> Builder<'a> for RefBuilder {
+    type Output = SExprRef<'a>;
+
+    fn atom(&mut self, atom: &'a str, _start: usize, _end: usize) -> SExprRef<'a> {
+        SExprRef::Atom(atom, classify_atom(atom))
+    }
+
+    fn text(&mut self, text: Cow<'a, str>, _start: usize, _end: usize) -> SExprRef<'a> {
+        SExprRef::Text(text)
+    }
+
+    fn node(
+        &mut self,
+        name: &'a str,
+        items: Vec<SExprRef<'a>>,
+        _start: usize,
+        _end: usize,
+    ) -> SExprRef<'a> {
+        SExprRef::Node(name, items)
+    }
+
+    fn list(&mut self, items: Vec<SExprRef<'a>>, _start: usize, _end: usize) -> SExprRef<'a> {
+        SExprRef::List(items)
+    }
+}
+
+/// Builds `SExprItem`s alongside a parallel tree of their spans.
+pub(crate) struct SpannedBuilder<'a> {
+    pub(crate) lines: LineIndex<'a>,
+}
+
+impl<'a> Builder<'a> for SpannedBuilder<'_> {
+    type Output = Spanned<SExprItem>;
+
+    fn atom(&mut self, atom: &'a str, start: usize, end: usize) -> Self::Output {
+        Spanned {
+            item: SExprItem::Atom(atom.to_owned()),
+            spans: SpanTree {
+                span: self.lines.span(start, end),
+                children: Vec::new(),
+            },
         }
     }
-    String::from_utf8(buf).map_err(|e| SExprError::Utf8 {
-        source: e,
+
+    fn text(&mut self, text: Cow<'a, str>, start: usize, end: usize) -> Self::Output {
+        Spanned {
+            item: SExprItem::Text(text.into_owned()),
+            spans: SpanTree {
+                span: self.lines.span(start, end),
+                children: Vec::new(),
+            },
+        }
+    }
+
+    fn node(
+        &mut self,
+        name: &'a str,
+        items: Vec<Self::Output>,
+        start: usize,
+        end: usize,
+    ) -> Self::Output {
+        let (items, children) = items
+            .into_iter()
+            .map(|spanned| (spanned.item, spanned.spans))
+            .unzip();
+        Spanned {
+            item: SExprItem::Node(name.to_owned(), items),
+            spans: SpanTree {
+                span: self.lines.span(start, end),
+                children,
+            },
+        }
+    }
+
+    fn list(&mut self, items: Vec<Self::Output>, start: usize, end: usize) -> Self::Output {
+        let (items, children) = items
+            .into_iter()
+            .map(|spanned| (spanned.item, spanned.spans))
+            .unzip();
+        Spanned {
+            item: SExprItem::List(items),
+            spans: SpanTree {
+                span: self.lines.span(start, end),
+                children,
+            },
+        }
+    }
+}
+
+fn read_one_byte(r: &mut Reader) -> Result<u8, SExprError> {
+    r.next_byte().ok_or_else(|| SExprError::Io {
+        source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
         position: r.position(),
     })
 }
 
-fn ensure_item_boundary(r: &Reader) -> Result<(), SExprError> {
+/// Returns the input from `start` up to the current position.
+fn read_slice<'a>(r: &Reader<'a>, start: usize) -> Result<&'a str, SExprError> {
+    // Only ever called at ASCII delimiters, so the slice is always on character boundaries
+    r.slice(start, r.position()).ok_or_else(|| SExprError::Io {
+        source: std::io::Error::from(std::io::ErrorKind::InvalidData),
+        position: start,
+    })
+}
+
+pub(crate) fn read_symbol<'a>(r: &mut Reader<'a>) -> Result<&'a str, SExprError> {
+    let start = r.position();
+    if r.options().quoted_atoms() && r.peek() == Some(b'|') {
+        if !r.skip_quoted_atom() {
+            return Err(SExprError::Io {
+                source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
+                position: r.position(),
+            });
+        }
+    } else {
+        r.skip_symbol();
+    }
+    read_slice(r, start)
+}
+
+pub(crate) fn ensure_item_boundary(r: &Reader) -> Result<(), SExprError> {
     match r.peek() {
         None | Some(b')') => Ok(()),
         Some(c) if c.is_ascii_whitespace() => Ok(()),
+        Some(_) if r.at_comment() => Ok(()),
         Some(unexpected) => Err(SExprError::MissingSeparator {
             unexpected,
             position: r.position(),
@@ -55,6 +192,7 @@ fn ensure_item_boundary(r: &Reader) -> Result<(), SExprError> {
 }
 
 fn read_required_byte(r: &mut Reader, expected: u8) -> Result<(), SExprError> {
+    let position = r.position();
     let byte = read_one_byte(r)?;
     if byte == expected {
         Ok(())
@@ -62,45 +200,182 @@ fn read_required_byte(r: &mut Reader, expected: u8) -> Result<(), SExprError> {
         Err(SExprError::UnexpectedByte {
             unexpected: byte,
             expected,
-            position: r.position(),
+            position,
         })
     }
 }
 
-fn read_text(r: &mut Reader) -> Result<String, SExprError> {
-    let mut buf = Vec::new();
-    // Check first item is quote
-    read_required_byte(r, b'"').map_err(|err| err.with_context("parsing beginning of text"))?;
-    loop {
-        let escaped = match r.peek() {
-            Some(b'\\') => {
-                // Escape sequence
+/// Reads up to `count` digits in the given radix, accumulating them onto `value`. Returns `None`
+/// if there are fewer digits or the value overflows.
+fn read_digits(r: &mut Reader, radix: u32, count: usize, mut value: u32) -> Option<u32> {
+    for _ in 0..count {
+        let digit = char::from(r.peek()?).to_digit(radix)?;
+        r.advance(1).ok()?;
+        value = value.checked_mul(radix)?.checked_add(digit)?;
+    }
+    Some(value)
+}
+
+/// Reads the `{...}` part of a `\u{...}` escape, returning the character it names.
+fn read_unicode_escape(r: &mut Reader) -> Option<char> {
+    if r.peek()? != b'{' {
+        return None;
+    }
+    r.advance(1).ok()?;
+    let mut value = 0u32;
+    let mut digits = 0usize;
+    while r.peek()? != b'}' {
+        // Unicode scalar values have at most six hex digits
+        if digits >= 6 {
+            return None;
+        }
+        value = read_digits(r, 16, 1, value)?;
+        digits = digits.saturating_add(1);
+    }
+    r.advance(1).ok()?;
+    if digits == 0 {
+        return None;
+    }
+    char::from_u32(value)
+}
+
+/// Decodes an escape sequence in text, following the backslash that has just been read, and
+/// appends the bytes it stands for.
+///
+/// Accepts the escapes of OCaml string literals, which SpecTec's `String.escaped` output is
+/// written in: `\\`, `\"`, `\'`, `\n`, `\t`, `\b`, `\r`, `\ ` (space), `\ddd` (decimal),
+/// `\xhh`, `\oOOO` (octal), `\u{h...}`, and a backslash at the end of a line, which skips the
+/// line break and the indentation after it.
+fn read_escape(r: &mut Reader, buf: &mut Vec<u8>) -> Result<(), SExprError> {
+    let position = r.position().saturating_sub(1);
+    let invalid = || SExprError::InvalidEscape { position };
+    match read_one_byte(r)? {
+        byte @ (b'\\' | b'"' | b'\'' | b' ') => buf.push(byte),
+        b'n' => buf.push(b'\n'),
+        b't' => buf.push(b'\t'),
+        b'b' => buf.push(0x08),
+        b'r' => buf.push(b'\r'),
+        digit @ b'0'..=b'9' => {
+            let value = read_digits(r, 10, 2, u32::from(digit.saturating_sub(b'0')));
+            buf.push(
+                value
+                    .and_then(|v| u8::try_from(v).ok())
+                    .ok_or_else(invalid)?,
+            );
+        }
+        b'x' => {
+            let value = read_digits(r, 16, 2, 0);
+            buf.push(
+                value
+                    .and_then(|v| u8::try_from(v).ok())
+                    .ok_or_else(invalid)?,
+            );
+        }
+        b'o' => {
+            let value = read_digits(r, 8, 3, 0);
+            buf.push(
+                value
+                    .and_then(|v| u8::try_from(v).ok())
+                    .ok_or_else(invalid)?,
+            );
+        }
+        b'u' => {
+            let c = read_unicode_escape(r).ok_or_else(invalid)?;
+            buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+        // A line continuation, whether the line ends in "\n" or "\r\n", also skips the
+        // indentation of the next line
+        newline @ (b'\n' | b'\r') => {
+            if newline == b'\r' && r.next_byte() != Some(b'\n') {
+                return Err(invalid());
+            }
+            while let Some(b' ' | b'\t') = r.peek() {
                 r.advance(1).map_err(|err| SExprError::Io {
                     source: err,
                     position: r.position(),
                 })?;
-                true
             }
-            Some(_)
-           | None // EOF - we'll handle with the read_one_byte call
-           => false,
-        };
-        let byte = read_one_byte(r)?;
-        if !escaped && byte == b'"' {
-            break;
         }
-        buf.push(byte);
+        _ => return Err(invalid()),
     }
-    String::from_utf8(buf).map_err(|e| SExprError::Utf8 {
-        source: e,
-        position: r.position(),
-    })
+    Ok(())
 }
 
-fn read_node(reader: &mut Reader) -> Result<SExprItem, SExprError> {
+pub(crate) fn read_text<'a>(r: &mut Reader<'a>) -> Result<Cow<'a, str>, SExprError> {
+    // Check first item is quote
+    read_required_byte(r, b'"').map_err(|err| err.with_context("parsing beginning of text"))?;
+    let start = r.position();
+    // Text without escapes can be borrowed directly from the input
+    if r.skip_text_run() == Some(b'"') {
+        let text = read_slice(r, start)?;
+        r.advance(1).map_err(|err| SExprError::Io {
+            source: err,
+            position: r.position(),
+        })?;
+        return Ok(Cow::Borrowed(text));
+    }
+    let mut buf = read_slice(r, start)?.as_bytes().to_vec();
+    loop {
+        match read_one_byte(r)? {
+            b'"' => break,
+            b'\\' => read_escape(r, &mut buf)?,
+            byte => buf.push(byte),
+        }
+        let run_start = r.position();
+        r.skip_text_run();
+        buf.extend_from_slice(r.byte_slice(run_start, r.position()));
+    }
+    String::from_utf8(buf)
+        .map(Cow::Owned)
+        .map_err(|e| SExprError::Utf8 {
+            source: e,
+            position: r.position(),
+        })
+}
+
+fn read_atom<'a, B: Builder<'a>>(
+    reader: &mut Reader<'a>,
+    builder: &mut B,
+) -> Result<B::Output, SExprError> {
+    let start = reader.position();
+    let atom = read_symbol(reader)?;
+    Ok(builder.atom(atom, start, reader.position()))
+}
+
+fn read_text_item<'a, B: Builder<'a>>(
+    reader: &mut Reader<'a>,
+    builder: &mut B,
+) -> Result<B::Output, SExprError> {
+    let start = reader.position();
+    let text = read_text(reader)?;
+    Ok(builder.text(text, start, reader.position()))
+}
+
+/// A node whose items are still being read. Lists have no name.
+pub(crate) struct OpenNode<'a, T> {
+    pub(crate) name: Option<&'a str>,
+    pub(crate) start: usize,
+    items: Vec<T>,
+}
+
+pub(crate) fn open_node<'a, T>(reader: &mut Reader<'a>) -> Result<OpenNode<'a, T>, SExprError> {
+    let start = reader.position();
     // Read opening '('
     read_required_byte(reader, b'(')
         .map_err(|err| err.with_context("parsing beginning of new node"))?;
+    if reader.options().lists() {
+        reader.consume_whitespace().map_err(|err| SExprError::Io {
+            source: err,
+            position: reader.position(),
+        })?;
+        if matches!(reader.peek(), None | Some(b'(' | b')' | b'"')) {
+            return Ok(OpenNode {
+                name: None,
+                start,
+                items: Vec::new(),
+            });
+        }
+    }
     // Read symbol
     let name = read_symbol(reader)?;
     if name.is_empty() {
@@ -109,13 +384,49 @@ fn read_node(reader: &mut Reader) -> Result<SExprItem, SExprError> {
         });
     }
     ensure_item_boundary(reader)?;
-    let mut items = Vec::new();
+    Ok(OpenNode {
+        name: Some(name),
+        start,
+        items: Vec::new(),
+    })
+}
+
+/// Fails if a node opened at `position` would be nested `depth` deep, counting a top-level node
+/// as depth 1, and that is deeper than `max_depth` allows.
+pub(crate) fn check_depth(
+    max_depth: usize,
+    depth: usize,
+    position: usize,
+) -> Result<(), SExprError> {
+    if depth > max_depth {
+        Err(SExprError::DepthLimitExceeded {
+            max_depth,
+            position,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads a node and everything nested within it.
+///
+/// Nodes that are still open are kept on an explicit stack rather than by recursion, so deeply
+/// nested input is reported as an error once it passes the configured maximum depth instead of
+/// overflowing the call stack.
+fn read_node<'a, B: Builder<'a>>(
+    reader: &mut Reader<'a>,
+    builder: &mut B,
+) -> Result<B::Output, SExprError> {
+    let max_depth = reader.options().max_depth();
+    check_depth(max_depth, 1, reader.position())?;
+    let mut node = open_node(reader)?;
+    let mut parents = Vec::new();
     loop {
         reader.consume_whitespace().map_err(|err| SExprError::Io {
             source: err,
             position: reader.position(),
         })?;
-        match reader.peek() {
+        let item = match reader.peek() {
             None => {
                 return Err(SExprError::Io {
                     source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
@@ -127,50 +438,79 @@ fn read_node(reader: &mut Reader) -> Result<SExprItem, SExprError> {
                     source: err,
                     position: reader.position(),
                 })?;
-                break;
+                let end = reader.position();
+                let closed = match node.name {
+                    Some(name) => builder.node(name, node.items, node.start, end),
+                    None => builder.list(node.items, node.start, end),
+                };
+                match parents.pop() {
+                    Some(parent) => {
+                        node = parent;
+                        closed
+                    }
+                    None => return Ok(closed),
+                }
             }
             Some(b'(') => {
-                items.push(read_node(reader)?);
-                ensure_item_boundary(reader)?;
+                // The new node is nested inside the current one, which is inside its parents
+                check_depth(
+                    max_depth,
+                    parents.len().saturating_add(2),
+                    reader.position(),
+                )?;
+                parents.push(std::mem::replace(&mut node, open_node(reader)?));
+                continue;
             }
-            Some(b'"') => {
-                items.push(SExprItem::Text(read_text(reader)?));
-                ensure_item_boundary(reader)?;
-            }
-            _ => {
-                items.push(SExprItem::Atom(read_symbol(reader)?));
-                ensure_item_boundary(reader)?;
-            }
-        }
+            Some(b'"') => read_text_item(reader, builder)?,
+            _ => read_atom(reader, builder)?,
+        };
+        ensure_item_boundary(reader)?;
+        node.items.push(item);
     }
-    Ok(SExprItem::Node(name, items))
 }
 
-pub fn parse_sexpr_stream(input: &str) -> Result<Vec<SExprItem>, SExprError> {
-    let mut reader = Reader::new(input);
-    let mut out = Vec::new();
-    loop {
-        reader.consume_whitespace().map_err(|err| SExprError::Io {
-            source: err,
+/// Reads the item starting at the current position, which must not be whitespace.
+pub(crate) fn read_item<'a, B: Builder<'a>>(
+    reader: &mut Reader<'a>,
+    builder: &mut B,
+) -> Result<B::Output, SExprError> {
+    match reader.peek() {
+        Some(b'(') => read_node(reader, builder),
+        Some(b'"') => read_text_item(reader, builder),
+        Some(b')') => Err(SExprError::UnexpectedByte {
+            unexpected: b')',
+            expected: b'(',
             position: reader.position(),
-        })?;
-        if reader.is_eof() {
-            break;
-        }
-        let item = match reader.peek() {
-            Some(b'(') => read_node(&mut reader),
-            Some(b'"') => Ok(SExprItem::Text(read_text(&mut reader)?)),
-            Some(_) => Ok(SExprItem::Atom(read_symbol(&mut reader)?)),
-            None => Err(SExprError::Io {
-                source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
-                position: reader.position(),
-            }),
-        }?;
-        out.push(item);
-        ensure_item_boundary(&reader)?;
+        }),
+        Some(_) => read_atom(reader, builder),
+        None => Err(SExprError::Io {
+            source: std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
+            position: reader.position(),
+        }),
     }
+}
+
+pub fn parse_sexpr_stream(input: &str) -> Result<Vec<SExprItem>, SExprError> {
+    ParserOptions::default().parse(input)
+}
+
+/// Parses the input like `parse_sexpr_stream`, borrowing atoms, node names and text from the
+/// input rather than copying them.
+pub fn parse_sexpr_stream_borrowed(input: &str) -> Result<Vec<SExprRef<'_>>, SExprError> {
+    ParserOptions::default().parse_borrowed(input)
+}
 
-    Ok(out)
+/// Parses the input like `parse_sexpr_stream`, additionally recording the span of every item.
+pub fn parse_sexpr_stream_spanned(input: &str) -> Result<Vec<Spanned<SExprItem>>, SExprError> {
+    ParserOptions::default().parse_spanned(input)
+}
+
+pub(crate) fn parse_with_builder<'a, B: Builder<'a>>(
+    input: &'a str,
+    options: ParserOptions,
+    builder: B,
+) -> Result<Vec<B::Output>, SExprError> {
+    BuiltItems::new(input, options, builder).collect()
 }
 
 #[cfg(test)]
@@ -187,6 +527,66 @@ mod test {
         assert_eq!(parsed, r#""he\l\lo\""#);
     }
 
+    #[test]
+    fn test_sexpr_parse_ocaml_escapes() {
+        let input = r#""a\nb\tc\rd\be\'f\ g\065\x42\o103\u{44}\u{1F600}\226\130\172\
+            h""#;
+        let parsed = read_text(&mut Reader::new(input)).unwrap();
+        assert_eq!(parsed, "a\nb\tc\rd\u{8}e'f gABCD\u{1F600}\u{20AC}h");
+        let parsed = read_text(&mut Reader::new("\"€ \\\" ü\\n\" rest")).unwrap();
+        assert_eq!(parsed, "€ \" ü\n");
+        let parsed = read_text(&mut Reader::new("\"a\\\r\n  b\"")).unwrap();
+        assert_eq!(parsed, "ab");
+
+        for (input, position) in [
+            (r#""\q""#, 1),
+            (r#""ab\256""#, 3),
+            (r#""\9""#, 1),
+            (r#""\xg0""#, 1),
+            (r#""\o400""#, 1),
+            (r#""\u{}""#, 1),
+            (r#""\u{110000}""#, 1),
+            (r#""\u{D800}""#, 1),
+            (r#""\u41""#, 1),
+            ("\"\\\rb\"", 1),
+        ] {
+            let err = read_text(&mut Reader::new(input)).unwrap_err();
+            assert!(
+                matches!(err, SExprError::InvalidEscape { position: p } if p == position),
+                "{input}: {err:?}"
+            );
+        }
+        assert!(matches!(
+            read_text(&mut Reader::new(r#""\255""#)),
+            Err(SExprError::Utf8 { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_borrowed() {
+        let input = r#"(typ "m" (inst "\"x\"" (alias nat)))"#;
+        let parsed = match parse_sexpr_stream_borrowed(input) {
+            Ok(p) => p,
+            Err(e) => panic!("{}", e),
+        };
+        let SExprRef::Node(name, items) = &parsed[0] else {
+            panic!("expected node: {parsed:?}");
+        };
+        assert!(std::ptr::eq(name.as_ptr(), input[1..].as_ptr()));
+        assert!(matches!(&items[0], SExprRef::Text(Cow::Borrowed("m"))));
+        let SExprRef::Node(_, inst) = &items[1] else {
+            panic!("expected node: {items:?}");
+        };
+        assert!(matches!(&inst[0], SExprRef::Text(Cow::Owned(t)) if t == "\"x\""));
+        assert_eq!(
+            parsed
+                .into_iter()
+                .map(SExprRef::into_owned)
+                .collect::<Vec<_>>(),
+            parse_sexpr_stream(input).unwrap()
+        );
+    }
+
     #[test]
     fn test_sexpr_parse() {
         let input = r#"(typ "m" (inst (alias nat)))"#;
@@ -228,6 +628,166 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_parse_spanned() {
+        let input = "(typ \"m\"\n  (inst (alias nat)))\nfoo";
+        let parsed = match parse_sexpr_stream_spanned(input) {
+            Ok(p) => p,
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(
+            parsed.iter().map(|s| s.item.clone()).collect::<Vec<_>>(),
+            parse_sexpr_stream(input).unwrap()
+        );
+
+        let typ = &parsed[0].spans;
+        assert_eq!((typ.span.start.offset, typ.span.end.offset), (0, 30));
+        assert_eq!((typ.span.end.line, typ.span.end.column), (2, 22));
+
+        let alias = typ.descendant(&[1, 0]).unwrap();
+        assert_eq!(
+            &input[alias.span.start.offset..alias.span.end.offset],
+            "(alias nat)"
+        );
+        assert_eq!((alias.span.start.line, alias.span.start.column), (2, 9));
+
+        let nat = alias.descendant(&[0]).unwrap();
+        assert_eq!((nat.span.start.line, nat.span.start.column), (2, 16));
+        assert!(nat.children.is_empty());
+
+        let foo = parsed[1].span();
+        assert_eq!((foo.start.line, foo.start.column), (3, 1));
+        assert_eq!(foo.end.offset, input.len());
+    }
+
+    #[test]
+    fn test_comments_are_skipped_when_enabled() {
+        let input = r#"; leading comment
+(typ "m" ; trailing comment
+  #| block #| nested |# comment |#
+  (inst (alias nat;comment directly after a symbol
+  )))
+"#;
+        let parsed = match ParserOptions::new().with_comments(true).parse(input) {
+            Ok(p) => p,
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(
+            parsed,
+            parse_sexpr_stream(r#"(typ "m" (inst (alias nat)))"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_comments_are_symbols_by_default() {
+        let input = "(a ; b)";
+        let parsed = match parse_sexpr_stream(input) {
+            Ok(p) => p,
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(
+            parsed,
+            vec![SExprItem::Node(
+                "a".to_string(),
+                vec![
+                    SExprItem::Atom(";".to_string()),
+                    SExprItem::Atom("b".to_string())
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_rejects_unterminated_block_comment() {
+        let input = "(a) #| no end";
+        assert!(
+            ParserOptions::new()
+                .with_comments(true)
+                .parse(input)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rejects_unbalanced_close() {
+        for input in [")", "(a))", "a )"] {
+            assert!(
+                matches!(
+                    parse_sexpr_stream(input),
+                    Err(SExprError::UnexpectedByte {
+                        unexpected: b')',
+                        ..
+                    })
+                ),
+                "expected unbalanced ')' to be rejected: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_deep_nesting_is_limited() {
+        let depth = 100_000;
+        let input = format!("{}{}", "(a ".repeat(depth), ")".repeat(depth));
+        assert!(matches!(
+            ParserOptions::new().with_max_depth(256).parse(&input),
+            Err(SExprError::DepthLimitExceeded {
+                max_depth: 256,
+                position: 768
+            })
+        ));
+
+        let depth = 5_000;
+        let input = format!("{}{}", "(a ".repeat(depth), ")".repeat(depth));
+        let options = ParserOptions::new().with_max_depth(depth);
+        let mut item = &options.parse(&input).unwrap()[0];
+        let mut parsed_depth = 1;
+        while let SExprItem::Node(_, items) = item
+            && let Some(child) = items.first()
+        {
+            item = child;
+            parsed_depth += 1;
+        }
+        assert_eq!(parsed_depth, depth);
+        // The depth is unlimited by default
+        assert!(parse_sexpr_stream(&input).is_ok());
+        assert!(
+            ParserOptions::new()
+                .with_max_depth(depth - 1)
+                .parse(&input)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_smallest_depth_limits() {
+        let depth_error = |result: Result<Vec<SExprItem>, SExprError>| match result {
+            Err(SExprError::DepthLimitExceeded { position, .. }) => Some(position),
+            Err(err) => panic!("expected a depth error: {err:?}"),
+            Ok(_) => None,
+        };
+        for (max_depth, input, position) in [
+            (0, "a \"b\"", None),
+            (0, "a (b)", Some(2)),
+            (1, "(a b) (c)", None),
+            (1, "(a (b))", Some(3)),
+            (2, "(a (b) (c d))", None),
+            (2, "(a (b (c)))", Some(6)),
+        ] {
+            let options = ParserOptions::new().with_max_depth(max_depth);
+            assert_eq!(depth_error(options.parse(input)), position, "{input}");
+            let events = options.events(input).collect::<Result<Vec<_>, _>>();
+            assert_eq!(depth_error(events.map(|_| Vec::new())), position, "{input}");
+
+            let encoded = crate::encode_sexpr_binary(&parse_sexpr_stream(input).unwrap());
+            // Positions in binary input are different, but the same nodes are too deep
+            assert_eq!(
+                depth_error(crate::decode_sexpr_binary_with_options(&encoded, &options)).is_some(),
+                position.is_some(),
+                "{input}"
+            );
+        }
+    }
+
     #[test]
     fn test_rejects_node_with_empty_name() {
         for input in ["()", "( )"] {
@@ -238,6 +798,51 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_lists_and_quoted_atoms_when_enabled() {
+        let input = "(module (func |$f x|) ()) ( (a) \"b\") |(not a node)| ( name\n x)";
+        let options = ParserOptions::new()
+            .with_lists(true)
+            .with_quoted_atoms(true);
+        let atom = |s: &str| SExprItem::Atom(s.to_string());
+        let node = |s: &str, items| SExprItem::Node(s.to_string(), items);
+        assert_eq!(
+            options.parse(input).unwrap(),
+            vec![
+                node(
+                    "module",
+                    vec![node("func", vec![atom("|$f x|")]), SExprItem::List(vec![])]
+                ),
+                SExprItem::List(vec![node("a", vec![]), SExprItem::Text("b".to_string())]),
+                atom("|(not a node)|"),
+                node("name", vec![atom("x")]),
+            ]
+        );
+        assert_eq!(
+            crate::parse_sexpr_stream_parallel_with_options(input, &options).unwrap(),
+            options.parse(input).unwrap()
+        );
+        assert_eq!(
+            options.parse("(|quoted name| x)").unwrap(),
+            vec![node("|quoted name|", vec![atom("x")])]
+        );
+
+        for input in ["(a |b", "|a|b", "(|a|(b))"] {
+            assert!(options.parse(input).is_err(), "{input:?}");
+        }
+        // Without the options, bars are ordinary symbol characters
+        assert_eq!(
+            parse_sexpr_stream("(a |b c|)").unwrap(),
+            vec![node("a", vec![atom("|b"), atom("c|")])]
+        );
+        assert!(
+            ParserOptions::new()
+                .with_quoted_atoms(true)
+                .parse("()")
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_rejects_missing_separator_between_node_name_and_text() {
         let input = r#"(typ"m")"#;
@@ -0,0 +1,253 @@
+use crate::sexpr::{AsSExpr, SExprView};
+use crate::write::write_text;
+
+/// Where the closing parenthesis of a node that doesn't fit on one line is placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClosingParen {
+    /// On its own line, at the indentation of the opening parenthesis, as SpecTec does.
+    #[default]
+    OwnLine,
+    /// Directly after the last item of the node.
+    SameLine,
+}
+
+/// Configures how S-expressions are laid out by the pretty-printer.
+///
+/// A node is written on a single line if it fits within the width, counted in characters,
+/// starting from its indentation. Otherwise its name stays on the first line and each of its
+/// items starts a new line, indented one level further. The defaults reproduce the layout of
+/// `spectec --ast` byte-for-byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrettyOptions {
+    width: usize,
+    indent: usize,
+    closing_paren: ClosingParen,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            indent: 2,
+            closing_paren: ClosingParen::OwnLine,
+        }
+    }
+}
+
+impl PrettyOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    #[must_use]
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    #[must_use]
+    pub fn with_closing_paren(mut self, closing_paren: ClosingParen) -> Self {
+        self.closing_paren = closing_paren;
+        self
+    }
+
+    /// Lays out a single item, without a trailing newline.
+    ///
+    /// As with `Display`, atoms are written verbatim; use `to_sexpr_string` to check that they can
+    /// be parsed back.
+    #[must_use]
+    pub fn format<S: AsSExpr>(&self, item: &S) -> String {
+        let mut out = String::new();
+        self.write_item(&mut out, item);
+        out
+    }
+
+    /// Lays out a stream of top-level items the way `spectec --ast` does: one after another, each
+    /// followed by a newline, with an empty line at the end.
+    #[must_use]
+    pub fn format_stream<S: AsSExpr>(&self, items: &[S]) -> String {
+        let mut out = String::new();
+        for item in items {
+            self.write_item(&mut out, item);
+            out.push('\n');
+        }
+        out.push('\n');
+        out
+    }
+
+    fn write_item<S: AsSExpr>(&self, out: &mut String, item: &S) {
+        let mut lengths = Vec::new();
+        flat_len(item, &mut lengths);
+        let mut lengths = lengths.into_iter();
+        self.layout(out, item, 0, &mut lengths);
+    }
+
+    /// Writes the item starting at `indent`, taking the single-line length of it and each of its
+    /// descendants in turn from `lengths`.
+    fn layout<S: AsSExpr>(
+        &self,
+        out: &mut String,
+        item: &S,
+        indent: usize,
+        lengths: &mut impl Iterator<Item = usize>,
+    ) {
+        let len = lengths.next().unwrap_or_default();
+        match item.view() {
+            SExprView::Atom(s) => out.push_str(s),
+            SExprView::Text(s) => {
+                // Writing to a String never fails
+                let _ = write_text(out, s);
+            }
+            SExprView::Node(name, items) => {
+                let fits = indent.saturating_add(len) <= self.width;
+                let child_indent = indent.saturating_add(self.indent);
+                out.push('(');
+                out.push_str(name);
+                for item in items {
+                    if fits {
+                        out.push(' ');
+                    } else {
+                        out.push('\n');
+                        push_spaces(out, child_indent);
+                    }
+                    self.layout(out, item, child_indent, lengths);
+                }
+                if !fits && self.closing_paren == ClosingParen::OwnLine {
+                    out.push('\n');
+                    push_spaces(out, indent);
+                }
+                out.push(')');
+            }
+            SExprView::List(items) => {
+                let fits = indent.saturating_add(len) <= self.width;
+                let child_indent = indent.saturating_add(self.indent);
+                out.push('(');
+                for (i, item) in items.iter().enumerate() {
+                    if !fits {
+                        out.push('\n');
+                        push_spaces(out, child_indent);
+                    } else if i > 0 {
+                        out.push(' ');
+                    }
+                    self.layout(out, item, child_indent, lengths);
+                }
+                if !fits && self.closing_paren == ClosingParen::OwnLine {
+                    out.push('\n');
+                    push_spaces(out, indent);
+                }
+                out.push(')');
+            }
+        }
+    }
+}
+
+fn push_spaces(out: &mut String, n: usize) {
+    out.extend(std::iter::repeat_n(' ', n));
+}
+
+/// Computes the length in characters of the item when written on a single line, recording it
+/// along with the lengths of all its descendants in pre-order.
+fn flat_len<S: AsSExpr>(item: &S, lengths: &mut Vec<usize>) -> usize {
+    let slot = lengths.len();
+    lengths.push(0);
+    let len = match item.view() {
+        SExprView::Atom(s) => s.chars().count(),
+        SExprView::Text(s) => {
+            let mut escaped = String::new();
+            // Writing to a String never fails
+            let _ = write_text(&mut escaped, s);
+            escaped.chars().count()
+        }
+        SExprView::Node(name, items) => items.iter().fold(
+            // Parentheses and name, then a space before each item
+            name.chars().count().saturating_add(2),
+            |len, item| {
+                len.saturating_add(1)
+                    .saturating_add(flat_len(item, lengths))
+            },
+        ),
+        // Parentheses, then a space between each item
+        SExprView::List(items) => items.iter().fold(
+            items.len().saturating_sub(1).saturating_add(2),
+            |len, item| len.saturating_add(flat_len(item, lengths)),
+        ),
+    };
+    if let Some(l) = lengths.get_mut(slot) {
+        *l = len;
+    }
+    len
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test_pretty_breaks_long_nodes() {
+        let input = r#"(def "min" (exp "nat" nat) (clause (exp "i" nat) (var "i") else))"#;
+        let items = parse_sexpr_stream(input).unwrap();
+        let options = PrettyOptions::new().with_width(30);
+        assert_eq!(
+            options.format_stream(&items),
+            r#"(def
+  "min"
+  (exp "nat" nat)
+  (clause
+    (exp "i" nat)
+    (var "i")
+    else
+  )
+)
+
+"#
+        );
+        assert_eq!(
+            options
+                .with_width(40)
+                .with_indent(1)
+                .with_closing_paren(ClosingParen::SameLine)
+                .format(&items[0]),
+            r#"(def
+ "min"
+ (exp "nat" nat)
+ (clause (exp "i" nat) (var "i") else))"#
+        );
+        assert_eq!(PrettyOptions::new().format(&items[0]), input);
+    }
+
+    #[test]
+    fn test_pretty_width_counts_characters() {
+        let items = parse_sexpr_stream("(a ééééé) (é \"ü\")").unwrap();
+        let options = PrettyOptions::new().with_width(10);
+        assert_eq!(options.format(&items[0]), "(a ééééé)");
+        assert_eq!(options.with_width(8).format(&items[0]), "(a\n  ééééé\n)");
+        // Text is written with non-ASCII bytes escaped, and is measured that way
+        assert_eq!(options.format(&items[1]), "(é\n  \"\\195\\188\"\n)");
+        assert_eq!(
+            options.with_width(14).format(&items[1]),
+            "(é \"\\195\\188\")"
+        );
+    }
+
+    #[test]
+    fn test_pretty_breaks_long_lists() {
+        let input = r#"((exp "nat" nat) () (var "i"))"#;
+        let items = ParserOptions::new().with_lists(true).parse(input).unwrap();
+        assert_eq!(
+            PrettyOptions::new().with_width(20).format(&items[0]),
+            r#"(
+  (exp "nat" nat)
+  ()
+  (var "i")
+)"#
+        );
+        assert_eq!(PrettyOptions::new().format(&items[0]), input);
+    }
+}
@@ -1,15 +1,47 @@
 #![allow(unused)]
 
+use crate::options::ParserOptions;
+use memchr::{memchr, memchr2};
 use std::io::Read;
 
+/// Which bytes end a symbol, as for `Reader::is_delimiter`, without and with comments enabled.
+const DELIMITERS: [[bool; 256]; 2] = [delimiters(false), delimiters(true)];
+
+const fn delimiters(comments: bool) -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut c = 0;
+    while c < 256 {
+        #[allow(clippy::cast_possible_truncation)]
+        let byte = c as u8;
+        table[c] = byte.is_ascii_whitespace()
+            || matches!(byte, b'(' | b')' | b'"')
+            || (comments && byte == b';');
+        c = c.wrapping_add(1);
+    }
+    table
+}
+
 pub(crate) struct Reader<'a> {
     data: &'a str,
     position: usize,
+    options: ParserOptions,
 }
 
 impl<'a> Reader<'a> {
     pub(crate) fn new(data: &'a str) -> Self {
-        Self { data, position: 0 }
+        Self::with_options(data, ParserOptions::default())
+    }
+
+    pub(crate) fn with_options(data: &'a str, options: ParserOptions) -> Self {
+        Self {
+            data,
+            position: 0,
+            options,
+        }
+    }
+
+    pub(crate) fn options(&self) -> ParserOptions {
+        self.options
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -24,6 +56,15 @@ impl<'a> Reader<'a> {
         self.position = pos;
     }
 
+    pub(crate) fn slice(&self, start: usize, end: usize) -> Option<&'a str> {
+        self.data.get(start..end)
+    }
+
+    /// Like `slice`, but without requiring the range to be on character boundaries.
+    pub(crate) fn byte_slice(&self, start: usize, end: usize) -> &'a [u8] {
+        self.data.as_bytes().get(start..end).unwrap_or_default()
+    }
+
     pub(crate) fn advance(&mut self, n: usize) -> std::io::Result<()> {
         self.position = self
             .position
@@ -40,20 +81,137 @@ impl<'a> Reader<'a> {
         self.data.as_bytes().get(self.position).copied()
     }
 
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.data
+            .as_bytes()
+            .get(self.position.checked_add(offset)?)
+            .copied()
+    }
+
+    /// Whether the byte ends a symbol.
+    pub(crate) fn is_delimiter(&self, c: u8) -> bool {
+        c.is_ascii_whitespace()
+            || c == b'('
+            || c == b')'
+            || c == b'"'
+            || (self.options.comments() && c == b';')
+    }
+
+    /// Whether a comment starts at the current position.
+    pub(crate) fn at_comment(&self) -> bool {
+        self.options.comments()
+            && match self.peek() {
+                Some(b';') => true,
+                Some(b'#') => self.peek_at(1) == Some(b'|'),
+                _ => false,
+            }
+    }
+
+    /// The input from the current position onwards.
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        self.data
+            .as_bytes()
+            .get(self.position..)
+            .unwrap_or_default()
+    }
+
+    /// Moves forward by `n` bytes, which must be within the remaining input, so the position can't
+    /// overflow.
+    fn skip(&mut self, n: usize) {
+        self.position = self.position.saturating_add(n).min(self.data.len());
+    }
+
+    /// Reads the byte at the current position and moves past it.
+    pub(crate) fn next_byte(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.skip(1);
+        Some(byte)
+    }
+
+    /// Moves forward to the first byte matching `stop`, or to the end of the input.
+    fn skip_until(&mut self, stop: impl Fn(u8) -> bool) {
+        let rest = self.remaining();
+        self.skip(rest.iter().position(|&c| stop(c)).unwrap_or(rest.len()));
+    }
+
+    /// Moves forward to the next delimiter, or to the end of the input.
+    pub(crate) fn skip_symbol(&mut self) {
+        let rest = self.remaining();
+        let delimiters = &DELIMITERS[usize::from(self.options.comments())];
+        self.skip(
+            rest.iter()
+                .position(|&c| delimiters[usize::from(c)])
+                .unwrap_or(rest.len()),
+        );
+    }
+
+    /// Moves past the `|`-quoted atom starting at the current position, including both bars.
+    /// Returns `false` if there is no closing bar, leaving the position at the end of the input.
+    pub(crate) fn skip_quoted_atom(&mut self) -> bool {
+        let rest = self.remaining();
+        if let Some(index) = rest.get(1..).and_then(|quoted| memchr(b'|', quoted)) {
+            self.skip(index.saturating_add(2));
+            true
+        } else {
+            self.skip(rest.len());
+            false
+        }
+    }
+
+    /// Moves forward to the next quote or backslash, returning it, or to the end of the input.
+    pub(crate) fn skip_text_run(&mut self) -> Option<u8> {
+        let rest = self.remaining();
+        let index = memchr2(b'"', b'\\', rest).unwrap_or(rest.len());
+        self.skip(index);
+        rest.get(index).copied()
+    }
+
     pub(crate) fn consume_whitespace(&mut self) -> std::io::Result<()> {
         loop {
-            if let Some(c) = self.peek()
-                && c.is_ascii_whitespace()
-            {
-                self.position = self
-                    .position
-                    .checked_add(1)
-                    .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+            self.skip_until(|c| !c.is_ascii_whitespace());
+            if self.at_comment() {
+                self.consume_comment()?;
             } else {
-                break;
+                return Ok(());
+            }
+        }
+    }
+
+    fn consume_comment(&mut self) -> std::io::Result<()> {
+        if self.peek() == Some(b';') {
+            // Line comment, the newline itself is left as whitespace
+            let rest = self.remaining();
+            self.skip(memchr(b'\n', rest).unwrap_or(rest.len()));
+            return Ok(());
+        }
+        // Block comment, which may contain nested block comments
+        let mut depth = 0usize;
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(b'#'), Some(b'|')) => {
+                    depth = depth
+                        .checked_add(1)
+                        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+                    self.advance(2)?;
+                }
+                (Some(b'|'), Some(b'#')) => {
+                    self.advance(2)?;
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                (Some(_), _) => {
+                    // Only `#` and `|` can start a delimiter, so everything before them is skipped
+                    self.advance(1)?;
+                    let rest = self.remaining();
+                    self.skip(memchr2(b'#', b'|', rest).unwrap_or(rest.len()));
+                }
+                (None, _) => {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+                }
             }
         }
-        Ok(())
     }
 }
 
@@ -0,0 +1,171 @@
+use crate::error::SExprError;
+use crate::iter::BuiltItems;
+use crate::options::ParserOptions;
+use crate::parse::ItemBuilder;
+use crate::scan::item_end;
+use crate::sexpr::SExprItem;
+use crate::stream::parse_chunk;
+
+/// Returns where to cut off an item that the scanner couldn't find the end of, because a closing
+/// parenthesis or quote is missing: the next `(` that starts a line, which is where SpecTec places
+/// every top-level item, or the end of the input.
+fn skip_unclosed_item(input: &str, start: usize) -> usize {
+    let item_start = input
+        .get(start..)
+        .and_then(|rest| rest.find(|c: char| !c.is_ascii_whitespace()))
+        .and_then(|i| start.checked_add(i))
+        .unwrap_or(input.len());
+    input
+        .get(item_start..)
+        .and_then(|rest| rest.find("\n("))
+        .and_then(|i| item_start.checked_add(i)?.checked_add(1))
+        .unwrap_or(input.len())
+}
+
+pub(crate) fn parse_recovering(
+    input: &str,
+    options: ParserOptions,
+) -> (Vec<SExprItem>, Vec<SExprError>) {
+    // Input that parses normally is never split, so recovery can't change its meaning
+    let mut items = Vec::new();
+    let Some(error) = BuiltItems::new(input, options, ItemBuilder)
+        .map(|item| item.map(|item| items.push(item)))
+        .find_map(Result::err)
+    else {
+        return (items, Vec::new());
+    };
+
+    items.clear();
+    let mut errors = Vec::new();
+    let mut start = 0;
+    let mut after_item = false;
+    while start < input.len() {
+        let end = item_end(input.as_bytes(), start, options)
+            .unwrap_or_else(|| skip_unclosed_item(input, start));
+        let chunk = input.get(start..end).unwrap_or_default();
+        match parse_chunk(chunk, options, after_item) {
+            Ok(item) => {
+                items.extend(item);
+                after_item = true;
+            }
+            Err(err) => {
+                errors.push(err.offset_by(start));
+                // The item is skipped, so whatever follows needn't be separated from it
+                after_item = false;
+            }
+        }
+        start = end;
+    }
+    // Splitting may move where errors are detected, but the input is known to have at least one
+    if errors.is_empty() {
+        errors.push(error);
+    }
+    (items, errors)
+}
+
+/// Parses the input like `parse_sexpr_stream`, but rather than stopping at the first syntax
+/// error, reports every error it can find along with all the items that could be parsed.
+///
+/// If the input is malformed, it is split into top-level items by balancing brackets, and each is
+/// parsed separately, so an error only loses the item it is in. An item whose closing parenthesis
+/// or quote is missing runs on to the next `(` at the start of a line, which is where SpecTec
+/// places every top-level item. Input that parses successfully returns the same items as
+/// `parse_sexpr_stream` and no errors.
+#[must_use]
+pub fn parse_sexpr_stream_recovering(input: &str) -> (Vec<SExprItem>, Vec<SExprError>) {
+    ParserOptions::default().parse_recovering(input)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    /// Returns the items recovered from the input, written out, and the position of each error.
+    fn recover(input: &str) -> (Vec<String>, Vec<String>) {
+        let (items, errors) = parse_sexpr_stream_recovering(input);
+        (
+            items.iter().map(ToString::to_string).collect(),
+            errors.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[test]
+    fn test_recovering_reports_every_error() {
+        let input = "(a) (b\"x\") (c)\n  (d \"\\q\")\n  (e)\n)\n(f";
+        assert_eq!(
+            recover(input),
+            (
+                vec!["(a)".to_owned(), "(c)".to_owned(), "(e)".to_owned()],
+                vec![
+                    "missing separator before byte 0x22 at 6".to_owned(),
+                    "invalid escape sequence at 21".to_owned(),
+                    "unexpected byte at 32: found 0x29, expected 0x28".to_owned(),
+                    "io error at 36: unexpected end of file".to_owned(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_recovering_splits_at_balanced_brackets() {
+        // Only the error is lost, as the brackets after it balance, even across lines
+        let input = r#"(typ "a" (inst (alias nat)))
+(typ"b")
+(typ "c" (inst (alias nat))
+(typ "d")
+)
+(typ "e")"#;
+        let (items, errors) = parse_sexpr_stream_recovering(input);
+        assert_eq!(
+            items,
+            parse_sexpr_stream(
+                r#"(typ "a" (inst (alias nat))) (typ "c" (inst (alias nat)) (typ "d")) (typ "e")"#
+            )
+            .unwrap()
+        );
+        assert!(
+            matches!(
+                errors[..],
+                [SExprError::MissingSeparator { position: 33, .. }]
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_recovering_cuts_off_unclosed_items() {
+        for (input, items, positions) in [
+            (
+                "(a (b) c\n(d)\n  (e f\n(g)",
+                &["(d)", "(g)"][..],
+                &[9, 20][..],
+            ),
+            ("(a (b\n(c) (d)", &["(c)", "(d)"], &[6]),
+            ("(a \"b)\n(c) (d)", &["(c)", "(d)"], &[7]),
+        ] {
+            let (parsed, errors) = parse_sexpr_stream_recovering(input);
+            assert_eq!(
+                parsed.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                items,
+                "{input:?}"
+            );
+            let error_positions = errors
+                .iter()
+                .map(|err| match err {
+                    SExprError::Io { position, .. } => *position,
+                    err => panic!("expected end of input: {err:?}"),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(error_positions, positions, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_recovering_valid_input() {
+        // Valid input is returned as parsed in one go, without looking for item boundaries
+        let input = "(a\n(b)\n)\n(c)";
+        let (items, errors) = parse_sexpr_stream_recovering(input);
+        assert_eq!(items, parse_sexpr_stream(input).unwrap());
+        assert!(errors.is_empty());
+    }
+}
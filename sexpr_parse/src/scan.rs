@@ -0,0 +1,192 @@
+use crate::options::ParserOptions;
+
+/// Where the scanner is within the current top-level item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// Between items, skipping whitespace.
+    Gap,
+    /// Inside a symbol.
+    Symbol,
+    /// Inside text, possibly just after a backslash.
+    Text {
+        escaped: bool,
+    },
+    /// Inside a symbol quoted in vertical bars, before the closing bar.
+    QuotedSymbol,
+    /// After a `#` that may start a block comment.
+    Hash,
+    LineComment,
+    /// Inside a block comment, with the nesting depth and the previous byte, to recognise the
+    /// two-byte `#|` and `|#` delimiters.
+    BlockComment {
+        depth: usize,
+        last: u8,
+    },
+}
+
+/// Where the current top-level item ends relative to the byte just scanned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Boundary {
+    /// The item continues past this byte.
+    None,
+    /// The item ends with this byte.
+    After,
+    /// The item ended just before this byte, which belongs to whatever follows.
+    Before,
+}
+
+/// Finds the end of each top-level item in a byte stream, without parsing it.
+///
+/// The scanner only tracks enough state to balance brackets while skipping over text and
+/// comments, so it can be fed a stream in pieces. Malformed input is never rejected here; the
+/// bytes up to the point the scanner gives up are left for the parser to report on.
+pub(crate) struct TopLevelScanner {
+    options: ParserOptions,
+    mode: Mode,
+    depth: usize,
+}
+
+impl TopLevelScanner {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            mode: Mode::Gap,
+            depth: 0,
+        }
+    }
+
+    fn is_delimiter(&self, b: u8) -> bool {
+        b.is_ascii_whitespace()
+            || b == b'('
+            || b == b')'
+            || b == b'"'
+            || (self.options.comments() && b == b';')
+    }
+
+    /// Scans one byte. After a boundary is reported, the scanner is ready for the next item,
+    /// and in the case of `Boundary::Before` the same byte must be scanned again.
+    pub(crate) fn scan(&mut self, b: u8) -> Boundary {
+        match self.mode {
+            Mode::Gap => self.scan_gap(b),
+            Mode::Symbol | Mode::Hash if self.is_delimiter(b) => {
+                self.mode = Mode::Gap;
+                if self.depth == 0 {
+                    Boundary::Before
+                } else {
+                    self.scan_gap(b)
+                }
+            }
+            Mode::Hash if b == b'|' => {
+                self.mode = Mode::BlockComment { depth: 1, last: 0 };
+                Boundary::None
+            }
+            Mode::Symbol | Mode::Hash => {
+                self.mode = Mode::Symbol;
+                Boundary::None
+            }
+            Mode::Text { escaped: true } => {
+                self.mode = Mode::Text { escaped: false };
+                Boundary::None
+            }
+            Mode::Text { escaped: false } => match b {
+                b'\\' => {
+                    self.mode = Mode::Text { escaped: true };
+                    Boundary::None
+                }
+                b'"' => {
+                    self.mode = Mode::Gap;
+                    self.end_if_top_level()
+                }
+                _ => Boundary::None,
+            },
+            Mode::QuotedSymbol => {
+                // The symbol ends at the closing bar, so anything directly after it is left for the
+                // parser to reject as a missing separator
+                if b == b'|' {
+                    self.mode = Mode::Gap;
+                    self.end_if_top_level()
+                } else {
+                    Boundary::None
+                }
+            }
+            Mode::LineComment => {
+                if b == b'\n' {
+                    self.mode = Mode::Gap;
+                }
+                Boundary::None
+            }
+            Mode::BlockComment { depth, last } => {
+                self.mode = match (last, b) {
+                    (b'#', b'|') => Mode::BlockComment {
+                        depth: depth.saturating_add(1),
+                        last: 0,
+                    },
+                    (b'|', b'#') if depth <= 1 => Mode::Gap,
+                    (b'|', b'#') => Mode::BlockComment {
+                        depth: depth.saturating_sub(1),
+                        last: 0,
+                    },
+                    _ => Mode::BlockComment { depth, last: b },
+                };
+                Boundary::None
+            }
+        }
+    }
+
+    fn scan_gap(&mut self, b: u8) -> Boundary {
+        match b {
+            _ if b.is_ascii_whitespace() => Boundary::None,
+            b'(' => {
+                self.depth = self.depth.saturating_add(1);
+                Boundary::None
+            }
+            b')' => {
+                // An unbalanced `)` at the top level is left for the parser to reject
+                self.depth = self.depth.saturating_sub(1);
+                self.end_if_top_level()
+            }
+            b'"' => {
+                self.mode = Mode::Text { escaped: false };
+                Boundary::None
+            }
+            b'|' if self.options.quoted_atoms() => {
+                self.mode = Mode::QuotedSymbol;
+                Boundary::None
+            }
+            b';' if self.options.comments() => {
+                self.mode = Mode::LineComment;
+                Boundary::None
+            }
+            b'#' if self.options.comments() => {
+                self.mode = Mode::Hash;
+                Boundary::None
+            }
+            _ => {
+                self.mode = Mode::Symbol;
+                Boundary::None
+            }
+        }
+    }
+
+    fn end_if_top_level(&self) -> Boundary {
+        if self.depth == 0 {
+            Boundary::After
+        } else {
+            Boundary::None
+        }
+    }
+}
+
+/// Returns the end of the first top-level item at or after `start`, or `None` if the input ends
+/// before the scanner finds one.
+pub(crate) fn item_end(input: &[u8], start: usize, options: ParserOptions) -> Option<usize> {
+    let mut scanner = TopLevelScanner::new(options);
+    for (i, &b) in input.iter().enumerate().skip(start) {
+        match scanner.scan(b) {
+            Boundary::None => {}
+            Boundary::After => return i.checked_add(1),
+            Boundary::Before => return Some(i),
+        }
+    }
+    None
+}
@@ -0,0 +1,396 @@
+use crate::error::SelectorError;
+use crate::sexpr::{AsSExpr, SExprView};
+use std::str::FromStr;
+
+/// How a step relates to the items selected by the previous step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Axis {
+    /// `/`: direct children.
+    Child,
+    /// `//`: children, grandchildren and so on.
+    Descendant,
+}
+
+/// Which items a step selects, before its predicates are checked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Test {
+    /// `*`: any item.
+    Any,
+    /// A number: the item at that index among its parent's children.
+    Index(usize),
+    /// A name: nodes with that name.
+    Node(String),
+}
+
+/// A value compared against a child in a predicate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Literal {
+    /// `"..."`: text with exactly this content.
+    Text(String),
+    /// A bare name: an atom with this value, or a node with this name.
+    Symbol(String),
+}
+
+/// `[index]` or `[index=literal]`: the item has a child at the index, which matches the literal
+/// if one is given.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Predicate {
+    index: usize,
+    value: Option<Literal>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Step {
+    axis: Axis,
+    test: Test,
+    predicates: Vec<Predicate>,
+}
+
+/// A compiled query selecting items from a forest of S-expressions.
+///
+/// A selector is a sequence of steps, each separated from the last by `/` to select children or
+/// `//` to select descendants at any depth. The first step applies to the top-level items, or
+/// to items at any depth if the selector starts with `//`. Each step is one of:
+///
+/// - a name, such as `typ`, selecting nodes with that name;
+/// - a number, such as `0`, selecting the child at that index;
+/// - `*`, selecting any item;
+///
+/// optionally followed by predicates that the selected items must satisfy:
+///
+/// - `[1]`: the item has a child at index 1;
+/// - `[0="instr"]`: the child at index 0 is the text `instr`;
+/// - `[0=nat]`: the child at index 0 is the atom `nat`, or a node named `nat`.
+///
+/// For example, `typ[0="instr"]//case/0` selects the first child of every `case` node within a
+/// top-level `(typ "instr" ...)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+/// Parses the selector syntax, keeping track of the position for errors.
+struct SelectorParser<'s> {
+    input: &'s str,
+    position: usize,
+}
+
+impl SelectorParser<'_> {
+    fn error(&self, message: &'static str) -> SelectorError {
+        SelectorError {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.position..)?.chars().next()
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        let matched = self
+            .input
+            .get(self.position..)
+            .is_some_and(|rest| rest.starts_with(prefix));
+        if matched {
+            self.position = self.position.saturating_add(prefix.len());
+        }
+        matched
+    }
+
+    /// Reads a run of characters that can appear in a name or number.
+    fn word(&mut self) -> &str {
+        let rest = self.input.get(self.position..).unwrap_or_default();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "/[]=*\"()".contains(c))
+            .unwrap_or(rest.len());
+        self.position = self.position.saturating_add(len);
+        rest.get(..len).unwrap_or_default()
+    }
+
+    fn index(&mut self) -> Result<usize, SelectorError> {
+        let start = self.position;
+        self.word().parse().map_err(|_| SelectorError {
+            position: start,
+            message: "expected an index",
+        })
+    }
+
+    fn text(&mut self) -> Result<String, SelectorError> {
+        let mut text = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.position = self.position.saturating_add(c.len_utf8());
+            match c {
+                _ if escaped => {
+                    text.push(c);
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                '"' => return Ok(text),
+                _ => text.push(c),
+            }
+        }
+        Err(self.error("unterminated text"))
+    }
+
+    fn literal(&mut self) -> Result<Literal, SelectorError> {
+        if self.eat("\"") {
+            return Ok(Literal::Text(self.text()?));
+        }
+        match self.word() {
+            "" => Err(self.error("expected text or a name")),
+            word => Ok(Literal::Symbol(word.to_owned())),
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, SelectorError> {
+        let index = self.index()?;
+        let value = if self.eat("=") {
+            Some(self.literal()?)
+        } else {
+            None
+        };
+        if !self.eat("]") {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(Predicate { index, value })
+    }
+
+    fn step(&mut self, axis: Axis) -> Result<Step, SelectorError> {
+        let test = if self.eat("*") {
+            Test::Any
+        } else {
+            match self.word() {
+                "" => return Err(self.error("expected a name, an index or `*`")),
+                word if word.bytes().all(|b| b.is_ascii_digit()) => {
+                    Test::Index(word.parse().map_err(|_| self.error("index too large"))?)
+                }
+                word => Test::Node(word.to_owned()),
+            }
+        };
+        let mut predicates = Vec::new();
+        while self.eat("[") {
+            predicates.push(self.predicate()?);
+        }
+        Ok(Step {
+            axis,
+            test,
+            predicates,
+        })
+    }
+
+    fn axis(&mut self) -> Option<Axis> {
+        if self.eat("//") {
+            Some(Axis::Descendant)
+        } else if self.eat("/") {
+            Some(Axis::Child)
+        } else {
+            None
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, SelectorError> {
+        let axis = self.axis().unwrap_or(Axis::Child);
+        let mut steps = vec![self.step(axis)?];
+        while self.peek().is_some() {
+            let axis = self
+                .axis()
+                .ok_or_else(|| self.error("expected `/` or `//`"))?;
+            steps.push(self.step(axis)?);
+        }
+        Ok(Selector { steps })
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, SelectorError> {
+        SelectorParser {
+            input: s,
+            position: 0,
+        }
+        .selector()
+    }
+}
+
+/// A selected item and the path of indices leading to it. The first index is the position of its
+/// top-level item in the forest, and each index after that is the position of a child within its
+/// parent node.
+pub type Selected<'a, S> = (Vec<usize>, &'a S);
+
+fn children<'a, S: AsSExpr>(item: Option<&'a S>, forest: &'a [S]) -> &'a [S] {
+    match item.map(AsSExpr::view) {
+        None => forest,
+        Some(SExprView::Node(_, items) | SExprView::List(items)) => items,
+        Some(SExprView::Atom(_) | SExprView::Text(_)) => &[],
+    }
+}
+
+impl Literal {
+    fn matches<S: AsSExpr>(&self, item: &S) -> bool {
+        match (self, item.view()) {
+            (Literal::Text(expected), SExprView::Text(text)) => expected == text,
+            (Literal::Symbol(expected), SExprView::Atom(name) | SExprView::Node(name, _)) => {
+                expected == name
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Step {
+    fn matches<S: AsSExpr>(&self, index: usize, item: &S) -> bool {
+        let test = match &self.test {
+            Test::Any => true,
+            Test::Index(expected) => index == *expected,
+            Test::Node(expected) => {
+                matches!(item.view(), SExprView::Node(name, _) if name == expected)
+            }
+        };
+        test && self.predicates.iter().all(|predicate| {
+            let child = children(Some(item), &[]).get(predicate.index);
+            match (&predicate.value, child) {
+                (_, None) => false,
+                (None, Some(_)) => true,
+                (Some(value), Some(child)) => value.matches(child),
+            }
+        })
+    }
+
+    /// Adds the items this step selects from within `item` (or the forest, if `None`) to `out`.
+    fn select<'a, S: AsSExpr>(
+        &self,
+        path: &mut Vec<usize>,
+        item: Option<&'a S>,
+        forest: &'a [S],
+        out: &mut Vec<(Vec<usize>, Option<&'a S>)>,
+    ) {
+        for (index, child) in children(item, forest).iter().enumerate() {
+            path.push(index);
+            if self.matches(index, child) {
+                out.push((path.clone(), Some(child)));
+            }
+            if self.axis == Axis::Descendant {
+                self.select(path, Some(child), forest, out);
+            }
+            path.pop();
+        }
+    }
+}
+
+impl Selector {
+    /// Returns every item in the forest matching the selector, in the order they appear.
+    #[must_use]
+    pub fn select<'a, S: AsSExpr>(&self, forest: &'a [S]) -> Vec<Selected<'a, S>> {
+        // `None` stands for the forest itself, whose children are the top-level items
+        let mut current = vec![(Vec::new(), None)];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for (mut path, item) in current {
+                step.select(&mut path, item, forest, &mut next);
+            }
+            // Descendant steps from nested items can select the same item more than once
+            next.sort_by(|(a, _), (b, _)| a.cmp(b));
+            next.dedup_by(|(a, _), (b, _)| a == b);
+            current = next;
+        }
+        current
+            .into_iter()
+            .filter_map(|(path, item)| Some((path, item?)))
+            .collect()
+    }
+}
+
+/// Parses the selector and returns every item in the forest matching it. See `Selector`.
+pub fn select<'a, S: AsSExpr>(
+    forest: &'a [S],
+    selector: &str,
+) -> Result<Vec<Selected<'a, S>>, SelectorError> {
+    Ok(selector.parse::<Selector>()?.select(forest))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    const INPUT: &str = r#"
+(module "m"
+  (func "f" (param i32) (block "outer" (block "inner" (br 1))))
+  (func "g" (result i64)))
+(export "f" func)
+"#;
+
+    fn selected(selector: &str) -> Vec<(Vec<usize>, String)> {
+        let forest = parse_sexpr_stream(INPUT).unwrap();
+        select(&forest, selector)
+            .unwrap()
+            .into_iter()
+            .map(|(path, item)| (path, item.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_select_paths() {
+        assert_eq!(
+            selected(r#"module[0="m"]/func/0"#),
+            [
+                (vec![0, 1, 0], r#""f""#.to_string()),
+                (vec![0, 2, 0], r#""g""#.to_string()),
+            ]
+        );
+        assert_eq!(
+            selected("//block/0"),
+            [
+                (vec![0, 1, 2, 0], r#""outer""#.to_string()),
+                (vec![0, 1, 2, 1, 0], r#""inner""#.to_string()),
+            ]
+        );
+        assert_eq!(
+            selected("//func[1=param]/0"),
+            [(vec![0, 1, 0], r#""f""#.to_string())]
+        );
+        // A bare name matches an atom or the name of a node
+        assert_eq!(
+            selected("*[1=func]/0"),
+            [
+                (vec![0, 0], r#""m""#.to_string()),
+                (vec![1, 0], r#""f""#.to_string()),
+            ]
+        );
+        assert_eq!(selected("module/func[2]").len(), 1);
+        assert!(selected("module/func[3]").is_empty());
+        assert_eq!(selected("//block//block").len(), 1);
+        assert_eq!(selected("//func//*").len(), 12);
+        assert!(selected("func").is_empty());
+    }
+
+    #[test]
+    fn test_select_paths_lead_to_items() {
+        let forest = parse_sexpr_stream_spanned(INPUT).unwrap();
+        let items: Vec<_> = forest.iter().map(|spanned| spanned.item.clone()).collect();
+        let selected = select(&items, "//func//1").unwrap();
+        assert_eq!(selected.len(), 4);
+        for (path, item) in selected {
+            let span = forest[path[0]].spans.descendant(&path[1..]).unwrap().span;
+            assert_eq!(&INPUT[span.start.offset..span.end.offset], item.to_string());
+        }
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        for (selector, position) in [
+            ("", 0),
+            ("typ/", 4),
+            ("typ[x]", 4),
+            ("typ[0", 5),
+            ("typ[0=\"a]", 9),
+            ("typ[0=]", 6),
+            ("typ case", 3),
+        ] {
+            let err = selector.parse::<Selector>().unwrap_err();
+            assert_eq!(err.position, position, "{selector}: {err}");
+        }
+    }
+}
@@ -1,24 +1,129 @@
+use crate::atom::{AtomKind, classify_atom};
+use std::borrow::Cow;
 use std::fmt::Display;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum SExprItem {
+    /// An atom as it was written. Its `AtomKind` isn't stored, so `AsSExpr::atom_kind` works it
+    /// out again on each call.
     Atom(String),
     Text(String),
     Node(String, Vec<SExprItem>),
+    /// A list without a name, such as `()` or `((a) "b")`. Only parsed with
+    /// `ParserOptions::with_lists`.
+    List(Vec<SExprItem>),
 }
 
+/// Writes the item on a single line, escaping text. Use `to_sexpr_string` to also check that the
+/// output will parse back to the same item.
 impl Display for SExprItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::write::write_item(f, self)
+    }
+}
+
+/// An S-expression item borrowing from the input it was parsed from.
+///
+/// Text is only copied when it contained escape sequences that had to be decoded. Atoms are stored
+/// along with their classification, worked out as they are parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SExprRef<'a> {
+    Atom(&'a str, AtomKind),
+    Text(Cow<'a, str>),
+    Node(&'a str, Vec<SExprRef<'a>>),
+    List(Vec<SExprRef<'a>>),
+}
+
+impl SExprRef<'_> {
+    #[must_use]
+    pub fn to_owned_item(&self) -> SExprItem {
+        match self {
+            SExprRef::Atom(s, _) => SExprItem::Atom((*s).to_owned()),
+            SExprRef::Text(s) => SExprItem::Text(s.clone().into_owned()),
+            SExprRef::Node(name, items) => SExprItem::Node(
+                (*name).to_owned(),
+                items.iter().map(SExprRef::to_owned_item).collect(),
+            ),
+            SExprRef::List(items) => {
+                SExprItem::List(items.iter().map(SExprRef::to_owned_item).collect())
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn into_owned(self) -> SExprItem {
         match self {
-            SExprItem::Atom(s) => write!(f, "{s}"),
-            SExprItem::Text(s) => write!(f, "\"{s}\""),
-            SExprItem::Node(name, items) => {
-                write!(f, "({name}")?;
-                for item in items {
-                    write!(f, " {item}")?;
-                }
-                write!(f, ")")
+            SExprRef::Atom(s, _) => SExprItem::Atom(s.to_owned()),
+            SExprRef::Text(s) => SExprItem::Text(s.into_owned()),
+            SExprRef::Node(name, items) => SExprItem::Node(
+                name.to_owned(),
+                items.into_iter().map(SExprRef::into_owned).collect(),
+            ),
+            SExprRef::List(items) => {
+                SExprItem::List(items.into_iter().map(SExprRef::into_owned).collect())
             }
         }
     }
 }
+
+impl Display for SExprRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::write::write_item(f, self)
+    }
+}
+
+/// A borrowed view of a single S-expression item, independent of how the item is stored.
+#[derive(Debug)]
+pub enum SExprView<'a, T> {
+    Atom(&'a str),
+    Text(&'a str),
+    Node(&'a str, &'a [T]),
+    List(&'a [T]),
+}
+
+/// Implemented by each representation of S-expression items, so that consumers such as decoders
+/// can accept any of them.
+pub trait AsSExpr: std::fmt::Debug + Sized {
+    fn view(&self) -> SExprView<'_, Self>;
+
+    /// Classifies the item if it is an atom.
+    ///
+    /// Only `SExprRef` and `InternedItem` store the classification worked out as they are parsed.
+    /// `SExprItem` classifies the atom again on each call, so decode from `SExprRef`s, as
+    /// `parse_sexpr_stream_borrowed` returns, to classify each atom just once.
+    fn atom_kind(&self) -> Option<AtomKind> {
+        match self.view() {
+            SExprView::Atom(atom) => Some(classify_atom(atom)),
+            SExprView::Text(_) | SExprView::Node(..) | SExprView::List(_) => None,
+        }
+    }
+}
+
+impl AsSExpr for SExprItem {
+    fn view(&self) -> SExprView<'_, Self> {
+        match self {
+            SExprItem::Atom(s) => SExprView::Atom(s),
+            SExprItem::Text(s) => SExprView::Text(s),
+            SExprItem::Node(name, items) => SExprView::Node(name, items),
+            SExprItem::List(items) => SExprView::List(items),
+        }
+    }
+}
+
+impl AsSExpr for SExprRef<'_> {
+    fn view(&self) -> SExprView<'_, Self> {
+        match self {
+            SExprRef::Atom(s, _) => SExprView::Atom(s),
+            SExprRef::Text(s) => SExprView::Text(s),
+            SExprRef::Node(name, items) => SExprView::Node(name, items),
+            SExprRef::List(items) => SExprView::List(items),
+        }
+    }
+
+    fn atom_kind(&self) -> Option<AtomKind> {
+        match self {
+            SExprRef::Atom(_, kind) => Some(*kind),
+            SExprRef::Text(_) | SExprRef::Node(..) | SExprRef::List(_) => None,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+/// A location within the source text.
+///
+/// `line` and `column` are 1-based, and `column` counts characters rather than bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The range of source text covered by an item, from its first byte up to (but excluding) `end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// The span of an item, along with the spans of each of its children in order.
+///
+/// Atoms and text have no children. The children of a node or list correspond one-to-one with the
+/// items of `SExprItem::Node` or `SExprItem::List`, so a path of child indices into an `SExprItem`
+/// is also a path into its `SpanTree`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanTree {
+    pub span: Span,
+    pub children: Vec<SpanTree>,
+}
+
+impl SpanTree {
+    /// Follows a path of child indices, returning the span tree of the item it leads to.
+    #[must_use]
+    pub fn descendant(&self, path: &[usize]) -> Option<&SpanTree> {
+        path.iter()
+            .try_fold(self, |tree, &index| tree.children.get(index))
+    }
+}
+
+/// An item paired with the source spans of itself and all of its descendants.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub item: T,
+    pub spans: SpanTree,
+}
+
+impl<T> Spanned<T> {
+    #[must_use]
+    pub fn span(&self) -> Span {
+        self.spans.span
+    }
+
+    #[must_use]
+    pub fn into_item(self) -> T {
+        self.item
+    }
+}
+
+/// How many bytes apart `LineIndex` records the number of characters so far.
+const CHAR_COUNT_INTERVAL: usize = 64;
+
+/// Converts byte offsets within a string into line and column positions.
+#[derive(Clone, Debug)]
+pub struct LineIndex<'a> {
+    input: &'a str,
+    line_starts: Vec<usize>,
+    /// The number of characters before every `CHAR_COUNT_INTERVAL`th byte, so that columns can be
+    /// found without counting from the start of a line, which may be very long.
+    char_counts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    #[must_use]
+    pub fn new(input: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        let mut char_counts = Vec::new();
+        let mut chars = 0usize;
+        for (i, b) in input.bytes().enumerate() {
+            if i.is_multiple_of(CHAR_COUNT_INTERVAL) {
+                char_counts.push(chars);
+            }
+            if !is_continuation_byte(b) {
+                chars = chars.saturating_add(1);
+            }
+            if b == b'\n' {
+                line_starts.push(i.saturating_add(1));
+            }
+        }
+        // The end of the input may itself be the start of a block
+        if input.len().is_multiple_of(CHAR_COUNT_INTERVAL) {
+            char_counts.push(chars);
+        }
+        Self {
+            input,
+            line_starts,
+            char_counts,
+        }
+    }
+
+    /// Counts the characters before a byte offset, which must be at a character boundary.
+    fn chars_before(&self, offset: usize) -> usize {
+        let block = offset / CHAR_COUNT_INTERVAL;
+        let block_start = block.saturating_mul(CHAR_COUNT_INTERVAL);
+        let counted = self.char_counts.get(block).copied().unwrap_or(0);
+        let rest = self
+            .input
+            .as_bytes()
+            .get(block_start..offset)
+            .unwrap_or_default();
+        counted.saturating_add(rest.iter().filter(|&&b| !is_continuation_byte(b)).count())
+    }
+
+    /// Returns the position of the given byte offset. Offsets past the end of the input are
+    /// clamped to the end, and offsets inside a multi-byte character are rounded down to its start.
+    #[must_use]
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.input.len());
+        while !self.input.is_char_boundary(offset) {
+            offset = offset.saturating_sub(1);
+        }
+        let line_index = self
+            .line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        let line_start = self.line_starts.get(line_index).copied().unwrap_or(0);
+        let column = self
+            .chars_before(offset)
+            .saturating_sub(self.chars_before(line_start));
+        Position {
+            offset,
+            line: line_index.saturating_add(1),
+            column: column.saturating_add(1),
+        }
+    }
+
+    #[must_use]
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+
+    /// Returns the text of the given 1-based line, without its line terminator.
+    #[must_use]
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        let index = line.checked_sub(1)?;
+        let start = *self.line_starts.get(index)?;
+        let end = index
+            .checked_add(1)
+            .and_then(|next| self.line_starts.get(next))
+            .copied()
+            .unwrap_or(self.input.len());
+        let text = self.input.get(start..end)?;
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+fn is_continuation_byte(b: u8) -> bool {
+    b & 0xC0 == 0x80
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test_columns_count_characters_on_long_lines() {
+        let line = "(a \"é⊤\" b𝕏c) ".repeat(40);
+        let input = format!("{line}\n{line}\r\nend");
+        for input in [input.as_str(), &line, &"é".repeat(64), ""] {
+            let lines = LineIndex::new(input);
+            for offset in 0..=input.len() {
+                let position = lines.position(offset);
+                let before = &input[..position.offset];
+                let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+                let column = before[line_start..].chars().count() + 1;
+                assert_eq!(position.column, column, "{offset}");
+                assert_eq!(position.line, before.matches('\n').count() + 1);
+            }
+        }
+        assert_eq!(LineIndex::new(&input).line(2), Some(line.as_str()));
+    }
+}
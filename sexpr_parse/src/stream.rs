@@ -0,0 +1,331 @@
+use crate::error::SExprError;
+use crate::options::ParserOptions;
+use crate::parse::{ItemBuilder, ensure_item_boundary, read_item};
+use crate::reader::Reader;
+use crate::scan::{Boundary, TopLevelScanner};
+use crate::sexpr::SExprItem;
+use std::io::BufRead;
+
+/// The I/O-free core of `SExprStreamReader` and `AsyncSExprStreamReader`, which buffers the bytes
+/// of the current top-level item until the scanner finds its end, then parses it.
+///
+/// A reader fills its buffer and passes the result to `feed` until `is_ready`, consuming as many
+/// bytes as `feed` returns each time, and then takes the item with `next_item`.
+pub(crate) struct StreamParser {
+    options: ParserOptions,
+    scanner: TopLevelScanner,
+    /// Bytes of the current item read so far.
+    bytes: Vec<u8>,
+    /// Offset of the start of `bytes` within the stream.
+    offset: usize,
+    /// Whether an item has been read, so the next one must be separated from it.
+    after_item: bool,
+    /// Whether the current item is complete, or the stream has ended, so it can be parsed.
+    ready: bool,
+    /// An error from reading the stream, to be returned in place of the next item.
+    error: Option<SExprError>,
+    /// Whether the stream has ended or an error has been returned, so no more items follow.
+    done: bool,
+}
+
+impl StreamParser {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            scanner: TopLevelScanner::new(options),
+            bytes: Vec::new(),
+            offset: 0,
+            after_item: false,
+            ready: false,
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Offset within the stream of the next byte to be read.
+    fn position(&self) -> usize {
+        self.offset.saturating_add(self.bytes.len())
+    }
+
+    /// Whether `next_item` can be called without feeding more of the stream.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready || self.done
+    }
+
+    /// Takes the result of filling the reader's buffer, returning how many bytes of it to consume.
+    /// An empty buffer is the end of the stream, and interrupted reads are ignored so that they
+    /// are retried.
+    pub(crate) fn feed(&mut self, available: std::io::Result<&[u8]>) -> usize {
+        match available {
+            Ok([]) => {
+                self.ready = true;
+                0
+            }
+            Ok(available) => {
+                let (used, complete) = self.extend(available);
+                self.ready = complete;
+                used
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => 0,
+            Err(source) => {
+                self.error = Some(SExprError::Io {
+                    source,
+                    position: self.position(),
+                });
+                self.ready = true;
+                0
+            }
+        }
+    }
+
+    /// Takes bytes from `available` up to the end of the current item, returning how many were
+    /// taken and whether the item is now complete.
+    fn extend(&mut self, available: &[u8]) -> (usize, bool) {
+        let mut end = None;
+        for (i, &b) in available.iter().enumerate() {
+            match self.scanner.scan(b) {
+                Boundary::None => {}
+                Boundary::After => {
+                    end = Some(i.saturating_add(1));
+                    break;
+                }
+                Boundary::Before => {
+                    end = Some(i);
+                    break;
+                }
+            }
+        }
+        let used = end.unwrap_or(available.len());
+        self.bytes
+            .extend_from_slice(available.get(..used).unwrap_or_default());
+        (used, end.is_some())
+    }
+
+    /// Returns the next top-level item, or `None` once the stream has ended or an error has been
+    /// returned. Only called once `is_ready`.
+    pub(crate) fn next_item(&mut self) -> Option<Result<SExprItem, SExprError>> {
+        if self.done {
+            return None;
+        }
+        self.ready = false;
+        let next = match self.error.take() {
+            Some(err) => Some(Err(err)),
+            None => self.finish().transpose(),
+        };
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+
+    /// Parses the item read so far and starts on the next one. Returns `None` if there was only
+    /// whitespace or comments before the end of the stream.
+    fn finish(&mut self) -> Result<Option<SExprItem>, SExprError> {
+        self.scanner = TopLevelScanner::new(self.options);
+        let bytes = std::mem::take(&mut self.bytes);
+        let len = bytes.len();
+        let chunk = String::from_utf8(bytes).map_err(|source| {
+            let valid_up_to = source.utf8_error().valid_up_to();
+            SExprError::Utf8 {
+                source,
+                position: self.offset.saturating_add(valid_up_to),
+            }
+        })?;
+        let item = parse_chunk(&chunk, self.options, self.after_item)
+            .map_err(|err| err.offset_by(self.offset))?;
+        self.offset = self.offset.saturating_add(len);
+        self.after_item = true;
+        Ok(item)
+    }
+}
+
+/// Parses the single item, if any, in a chunk of input that ends where `TopLevelScanner` found the
+/// end of an item, or at the end of the input. `after_item` is whether an item came before the
+/// chunk, which must then start with a separator.
+pub(crate) fn parse_chunk(
+    chunk: &str,
+    options: ParserOptions,
+    after_item: bool,
+) -> Result<Option<SExprItem>, SExprError> {
+    let mut reader = Reader::with_options(chunk, options);
+    if after_item {
+        ensure_item_boundary(&reader)?;
+    }
+    reader
+        .consume_whitespace()
+        .map_err(|source| SExprError::Io {
+            source,
+            position: reader.position(),
+        })?;
+    if reader.is_eof() {
+        return Ok(None);
+    }
+    let item = read_item(&mut reader, &mut ItemBuilder)?;
+    // The scanner ends the chunk after one item, so anything other than whitespace and
+    // comments left over must not follow it directly
+    ensure_item_boundary(&reader)?;
+    reader
+        .consume_whitespace()
+        .map_err(|source| SExprError::Io {
+            source,
+            position: reader.position(),
+        })?;
+    match reader.peek() {
+        None => Ok(Some(item)),
+        Some(unexpected) => Err(SExprError::MissingSeparator {
+            unexpected,
+            position: reader.position(),
+        }),
+    }
+}
+
+/// Parses top-level items one at a time from a `BufRead`, such as stdin, a file or a socket.
+///
+/// Only the bytes of the item currently being parsed are buffered. Items are returned in order,
+/// and iteration ends after the first error. Positions in errors are byte offsets from the start
+/// of the stream.
+pub struct SExprStreamReader<R> {
+    inner: R,
+    parser: StreamParser,
+}
+
+impl<R: BufRead> SExprStreamReader<R> {
+    #[must_use]
+    pub fn new(inner: R) -> Self {
+        Self::with_options(inner, ParserOptions::default())
+    }
+
+    #[must_use]
+    pub fn with_options(inner: R, options: ParserOptions) -> Self {
+        Self {
+            inner,
+            parser: StreamParser::new(options),
+        }
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: BufRead> Iterator for SExprStreamReader<R> {
+    type Item = Result<SExprItem, SExprError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.parser.is_ready() {
+            let used = self.parser.feed(self.inner.fill_buf());
+            self.inner.consume(used);
+        }
+        self.parser.next_item()
+    }
+}
+
+/// Parses top-level items one at a time from a reader. See `SExprStreamReader`.
+pub fn parse_sexpr_reader<R: BufRead>(reader: R) -> SExprStreamReader<R> {
+    SExprStreamReader::new(reader)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::io::BufReader;
+
+    /// Feeds the input a few bytes at a time, to exercise items split across reads.
+    fn stream(input: &str, options: ParserOptions) -> Vec<Result<SExprItem, SExprError>> {
+        let reader = BufReader::with_capacity(3, input.as_bytes());
+        SExprStreamReader::with_options(reader, options).collect()
+    }
+
+    #[test]
+    fn test_stream_matches_parse_sexpr_stream() {
+        let input = r#"
+(typ "m" (inst (alias nat)))
+foo "b\"a)r"
+(rel "R" (a (b) c))
+"#;
+        let streamed = stream(input, ParserOptions::default())
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(streamed, parse_sexpr_stream(input).unwrap());
+    }
+
+    #[test]
+    fn test_stream_skips_comments() {
+        let options = ParserOptions::new().with_comments(true);
+        let input = "; (a\n(b #| ) |# c) #| x |# d";
+        let streamed = stream(input, options)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(streamed, options.parse(input).unwrap());
+    }
+
+    #[test]
+    fn test_stream_errors_have_stream_positions() {
+        let quoted = ParserOptions::new().with_quoted_atoms(true);
+        for (input, options) in [
+            ("(a) (b)(c)", ParserOptions::default()),
+            ("(a) (b", ParserOptions::default()),
+            ("(a) (b))", ParserOptions::default()),
+            ("(a) b\"c\"", ParserOptions::default()),
+            ("|a b|c", quoted),
+            ("|a|(b)", quoted),
+            ("(x |a|b)", quoted),
+        ] {
+            let expected = options.parse(input).unwrap_err();
+            let streamed = stream(input, options);
+            let err = match streamed.last() {
+                Some(Err(err)) => err,
+                other => panic!("expected error for {input:?}, got {other:?}"),
+            };
+            assert_eq!(err.to_string(), expected.to_string(), "{input:?}");
+        }
+    }
+
+    /// Returns each of a sequence of reads in turn, then the end of the stream.
+    struct Reads(std::collections::VecDeque<std::io::Result<&'static [u8]>>);
+
+    impl std::io::Read for Reads {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.pop_front() {
+                Some(Ok(bytes)) => {
+                    buf[..bytes.len()].copy_from_slice(bytes);
+                    Ok(bytes.len())
+                }
+                Some(Err(err)) => Err(err),
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_retries_interrupted_reads() {
+        let reads = Reads(
+            [
+                Ok(&b"(a"[..]),
+                Err(std::io::ErrorKind::Interrupted.into()),
+                Ok(b") (b"),
+                Err(std::io::ErrorKind::BrokenPipe.into()),
+                Ok(b")"),
+            ]
+            .into(),
+        );
+        let mut reader = parse_sexpr_reader(BufReader::new(reads));
+        assert_eq!(reader.next().unwrap().unwrap().to_string(), "(a)");
+        assert!(matches!(
+            reader.next(),
+            Some(Err(SExprError::Io { position: 6, .. }))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_stream_stops_after_error() {
+        let mut reader = parse_sexpr_reader("(a) ) (b)".as_bytes());
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        assert!(matches!(reader.next(), Some(Err(_))));
+        assert!(reader.next().is_none());
+    }
+}
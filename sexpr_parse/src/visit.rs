@@ -0,0 +1,336 @@
+use crate::sexpr::{AsSExpr, SExprItem, SExprView};
+
+/// Hooks called while walking a forest of S-expressions with `visit`.
+///
+/// Every hook is given the depth of the item it's called for: 0 for top-level items, and one more
+/// than its parent for the items of a node or list. `name` is `None` for lists.
+pub trait SExprVisitor<S> {
+    fn atom(&mut self, _atom: &str, _depth: usize) {}
+
+    fn text(&mut self, _text: &str, _depth: usize) {}
+
+    /// Called before the items of a node or list are visited. Returning `false` skips them,
+    /// though `leave` is still called.
+    fn enter(&mut self, _name: Option<&str>, _items: &[S], _depth: usize) -> bool {
+        true
+    }
+
+    /// Called after the items of a node or list have been visited.
+    fn leave(&mut self, _name: Option<&str>, _items: &[S], _depth: usize) {}
+}
+
+/// Hooks called while walking a forest of `SExprItem`s with `visit_mut`, which may change the
+/// items in place.
+///
+/// As with `SExprVisitor`, `name` is `None` for lists. Items added to or removed from a node in
+/// `enter` are reflected in which items are visited next.
+pub trait SExprVisitorMut {
+    fn atom(&mut self, _atom: &mut String, _depth: usize) {}
+
+    fn text(&mut self, _text: &mut String, _depth: usize) {}
+
+    /// Called before the items of a node or list are visited. Returning `false` skips them,
+    /// though `leave` is still called.
+    fn enter(
+        &mut self,
+        _name: Option<&mut String>,
+        _items: &mut Vec<SExprItem>,
+        _depth: usize,
+    ) -> bool {
+        true
+    }
+
+    /// Called after the items of a node or list have been visited.
+    fn leave(&mut self, _name: Option<&mut String>, _items: &mut Vec<SExprItem>, _depth: usize) {}
+}
+
+/// Builds a value from each item of a forest, bottom-up, given the values already built from its
+/// items. Used with `fold`.
+pub trait SExprFold {
+    type Output;
+
+    fn atom(&mut self, atom: &str, depth: usize) -> Self::Output;
+    fn text(&mut self, text: &str, depth: usize) -> Self::Output;
+    fn node(&mut self, name: &str, items: Vec<Self::Output>, depth: usize) -> Self::Output;
+    fn list(&mut self, items: Vec<Self::Output>, depth: usize) -> Self::Output;
+}
+
+fn visit_item<S: AsSExpr, V: SExprVisitor<S> + ?Sized>(item: &S, visitor: &mut V, depth: usize) {
+    let (name, items) = match item.view() {
+        SExprView::Atom(atom) => return visitor.atom(atom, depth),
+        SExprView::Text(text) => return visitor.text(text, depth),
+        SExprView::Node(name, items) => (Some(name), items),
+        SExprView::List(items) => (None, items),
+    };
+    if visitor.enter(name, items, depth) {
+        for item in items {
+            visit_item(item, visitor, depth.saturating_add(1));
+        }
+    }
+    visitor.leave(name, items, depth);
+}
+
+/// Walks every item of the forest in the order they are written, calling the visitor's hooks.
+pub fn visit<S: AsSExpr, V: SExprVisitor<S> + ?Sized>(forest: &[S], visitor: &mut V) {
+    for item in forest {
+        visit_item(item, visitor, 0);
+    }
+}
+
+fn visit_item_mut<V: SExprVisitorMut + ?Sized>(
+    item: &mut SExprItem,
+    visitor: &mut V,
+    depth: usize,
+) {
+    let (mut name, items) = match item {
+        SExprItem::Atom(atom) => return visitor.atom(atom, depth),
+        SExprItem::Text(text) => return visitor.text(text, depth),
+        SExprItem::Node(name, items) => (Some(name), items),
+        SExprItem::List(items) => (None, items),
+    };
+    if visitor.enter(name.as_deref_mut(), items, depth) {
+        for item in items.iter_mut() {
+            visit_item_mut(item, visitor, depth.saturating_add(1));
+        }
+    }
+    visitor.leave(name, items, depth);
+}
+
+/// Walks every item of the forest in the order they are written, calling the visitor's hooks,
+/// which may change the items.
+pub fn visit_mut<V: SExprVisitorMut + ?Sized>(forest: &mut [SExprItem], visitor: &mut V) {
+    for item in forest {
+        visit_item_mut(item, visitor, 0);
+    }
+}
+
+fn fold_item<S: AsSExpr, F: SExprFold + ?Sized>(
+    item: &S,
+    folder: &mut F,
+    depth: usize,
+) -> F::Output {
+    match item.view() {
+        SExprView::Atom(atom) => folder.atom(atom, depth),
+        SExprView::Text(text) => folder.text(text, depth),
+        SExprView::Node(name, items) => {
+            let items = fold_items(items, folder, depth.saturating_add(1));
+            folder.node(name, items, depth)
+        }
+        SExprView::List(items) => {
+            let items = fold_items(items, folder, depth.saturating_add(1));
+            folder.list(items, depth)
+        }
+    }
+}
+
+fn fold_items<S: AsSExpr, F: SExprFold + ?Sized>(
+    items: &[S],
+    folder: &mut F,
+    depth: usize,
+) -> Vec<F::Output> {
+    items
+        .iter()
+        .map(|item| fold_item(item, folder, depth))
+        .collect()
+}
+
+/// Folds each top-level item of the forest, returning the results in order.
+///
+/// For example, a fold whose output is `SExprItem` can rebuild a forest with some atoms renamed,
+/// or convert from another representation such as `InternedItem`.
+pub fn fold<S: AsSExpr, F: SExprFold + ?Sized>(forest: &[S], folder: &mut F) -> Vec<F::Output> {
+    fold_items(forest, folder, 0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    const INPUT: &str = r#"
+(module "m" (func "f" (block (block (br 1) x))) (func "drop" y))
+(data "bytes")
+"#;
+
+    /// Records the hooks called, skipping the items of `func` nodes if asked to.
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+        skip_func: bool,
+    }
+
+    impl<S> SExprVisitor<S> for Recorder {
+        fn atom(&mut self, atom: &str, depth: usize) {
+            self.calls.push(format!("{depth} {atom}"));
+        }
+
+        fn text(&mut self, text: &str, depth: usize) {
+            self.calls.push(format!("{depth} {text:?}"));
+        }
+
+        fn enter(&mut self, name: Option<&str>, items: &[S], depth: usize) -> bool {
+            let name = name.unwrap_or("list");
+            self.calls.push(format!("{depth} ({name} {}", items.len()));
+            !(self.skip_func && name == "func")
+        }
+
+        fn leave(&mut self, name: Option<&str>, _items: &[S], depth: usize) {
+            self.calls
+                .push(format!("{depth} {})", name.unwrap_or("list")));
+        }
+    }
+
+    #[test]
+    fn test_visit_order_and_depth() {
+        let forest = parse_sexpr_stream(INPUT).unwrap();
+        let mut recorder = Recorder {
+            skip_func: true,
+            ..Recorder::default()
+        };
+        visit(&forest, &mut recorder);
+        assert_eq!(
+            recorder.calls,
+            [
+                "0 (module 3",
+                "1 \"m\"",
+                "1 (func 2",
+                "1 func)",
+                "1 (func 2",
+                "1 func)",
+                "0 module)",
+                "0 (data 1",
+                "1 \"bytes\"",
+                "0 data)",
+            ]
+        );
+
+        let mut recorder = Recorder::default();
+        let interned = parse_sexpr_stream_interned(INPUT, &mut Interner::new()).unwrap();
+        visit(&interned, &mut recorder);
+        assert_eq!(recorder.calls.len(), 21);
+        assert!(recorder.calls.contains(&"4 (br 1".to_string()));
+        assert!(recorder.calls.contains(&"5 1".to_string()));
+
+        let lists = ParserOptions::new()
+            .with_lists(true)
+            .parse("(() (a))")
+            .unwrap();
+        let mut recorder = Recorder::default();
+        visit(&lists, &mut recorder);
+        assert_eq!(
+            recorder.calls,
+            [
+                "0 (list 2",
+                "1 (list 0",
+                "1 list)",
+                "1 (a 0",
+                "1 a)",
+                "0 list)"
+            ]
+        );
+    }
+
+    /// Appends its depth to every atom, renames `block` nodes to `loop`, and removes every node
+    /// whose first item is the text "drop".
+    struct Edit;
+
+    impl SExprVisitorMut for Edit {
+        fn atom(&mut self, atom: &mut String, depth: usize) {
+            atom.push_str(&depth.to_string());
+        }
+
+        fn enter(
+            &mut self,
+            name: Option<&mut String>,
+            items: &mut Vec<SExprItem>,
+            _depth: usize,
+        ) -> bool {
+            items.retain(|item| {
+                !matches!(item, SExprItem::Node(_, case)
+                    if case.first() == Some(&SExprItem::Text("drop".to_string())))
+            });
+            if let Some(name) = name
+                && name == "block"
+            {
+                *name = "loop".to_string();
+            }
+            true
+        }
+    }
+
+    #[test]
+    fn test_visit_mut_edits_in_place() {
+        let mut forest = parse_sexpr_stream(INPUT).unwrap();
+        visit_mut(&mut forest, &mut Edit);
+        assert_eq!(
+            forest.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                r#"(module "m" (func "f" (loop (loop (br 15) x4))))"#,
+                r#"(data "bytes")"#,
+            ]
+        );
+    }
+
+    /// Rebuilds plain items, upper-casing atoms.
+    struct Rebuild;
+
+    impl SExprFold for Rebuild {
+        type Output = SExprItem;
+
+        fn atom(&mut self, atom: &str, _depth: usize) -> SExprItem {
+            SExprItem::Atom(atom.to_uppercase())
+        }
+
+        fn text(&mut self, text: &str, _depth: usize) -> SExprItem {
+            SExprItem::Text(text.to_owned())
+        }
+
+        fn node(&mut self, name: &str, items: Vec<SExprItem>, _depth: usize) -> SExprItem {
+            SExprItem::Node(name.to_owned(), items)
+        }
+
+        fn list(&mut self, items: Vec<SExprItem>, _depth: usize) -> SExprItem {
+            SExprItem::List(items)
+        }
+    }
+
+    /// Counts the items in each tree and the deepest depth reached.
+    struct Size;
+
+    impl SExprFold for Size {
+        type Output = (usize, usize);
+
+        fn atom(&mut self, _atom: &str, depth: usize) -> (usize, usize) {
+            (1, depth)
+        }
+
+        fn text(&mut self, _text: &str, depth: usize) -> (usize, usize) {
+            (1, depth)
+        }
+
+        fn node(
+            &mut self,
+            _name: &str,
+            items: Vec<(usize, usize)>,
+            depth: usize,
+        ) -> (usize, usize) {
+            items
+                .into_iter()
+                .fold((1, depth), |(n, d), (m, e)| (n.saturating_add(m), d.max(e)))
+        }
+
+        fn list(&mut self, items: Vec<(usize, usize)>, depth: usize) -> (usize, usize) {
+            self.node("", items, depth)
+        }
+    }
+
+    #[test]
+    fn test_fold_rebuilds_trees() {
+        let borrowed = parse_sexpr_stream_borrowed(INPUT).unwrap();
+        let rebuilt = fold(&borrowed, &mut Rebuild);
+        assert_eq!(
+            rebuilt,
+            parse_sexpr_stream(&INPUT.replace(" x)", " X)").replace(" y)", " Y)")).unwrap()
+        );
+        assert_eq!(fold(&rebuilt, &mut Size), [(12, 5), (2, 1)]);
+    }
+}
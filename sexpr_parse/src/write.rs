@@ -0,0 +1,175 @@
+use crate::error::SExprError;
+use crate::sexpr::{AsSExpr, SExprView};
+use std::fmt::Write;
+
+/// Whether an atom or node name can be written as-is and parsed back unchanged.
+#[must_use]
+pub fn is_valid_atom(atom: &str) -> bool {
+    !atom.is_empty()
+        && !atom
+            .bytes()
+            .any(|c| c.is_ascii_whitespace() || c == b'(' || c == b')' || c == b'"')
+}
+
+/// Writes text in quotes, escaped the way OCaml's `String.escaped` does it, as in SpecTec's
+/// output: printable ASCII is written as-is apart from quotes and backslashes, and any other byte
+/// without a named escape is written as three decimal digits.
+pub(crate) fn write_text<W: Write>(out: &mut W, text: &str) -> std::fmt::Result {
+    out.write_char('"')?;
+    for byte in text.bytes() {
+        match byte {
+            b'"' => out.write_str("\\\"")?,
+            b'\\' => out.write_str("\\\\")?,
+            b'\n' => out.write_str("\\n")?,
+            b'\t' => out.write_str("\\t")?,
+            b'\r' => out.write_str("\\r")?,
+            0x08 => out.write_str("\\b")?,
+            b' '..=b'~' => out.write_char(char::from(byte))?,
+            _ => write!(out, "\\{byte:03}")?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Writes an item on a single line. Atoms are written verbatim, so this only round-trips for
+/// items where every atom and node name is valid; see `to_sexpr_string`.
+pub(crate) fn write_item<S: AsSExpr, W: Write>(out: &mut W, item: &S) -> std::fmt::Result {
+    match item.view() {
+        SExprView::Atom(s) => out.write_str(s),
+        SExprView::Text(s) => write_text(out, s),
+        SExprView::Node(name, items) => {
+            write!(out, "({name}")?;
+            for item in items {
+                out.write_char(' ')?;
+                write_item(out, item)?;
+            }
+            out.write_char(')')
+        }
+        SExprView::List(items) => {
+            out.write_char('(')?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(' ')?;
+                }
+                write_item(out, item)?;
+            }
+            out.write_char(')')
+        }
+    }
+}
+
+/// Checks that every atom and node name within the item is valid, and that no list starts with an
+/// atom, which would be read back as a node.
+pub(crate) fn validate_atoms<S: AsSExpr>(item: &S) -> Result<(), SExprError> {
+    let check = |atom: &str| {
+        if is_valid_atom(atom) {
+            Ok(())
+        } else {
+            Err(SExprError::InvalidAtom {
+                atom: atom.to_owned(),
+            })
+        }
+    };
+    match item.view() {
+        SExprView::Atom(atom) => check(atom),
+        SExprView::Text(_) => Ok(()),
+        SExprView::Node(name, items) => {
+            check(name)?;
+            items.iter().try_for_each(validate_atoms)
+        }
+        SExprView::List(items) => {
+            if let Some(SExprView::Atom(atom)) = items.first().map(AsSExpr::view) {
+                return Err(SExprError::InvalidList {
+                    atom: atom.to_owned(),
+                });
+            }
+            items.iter().try_for_each(validate_atoms)
+        }
+    }
+}
+
+/// Serialises an item such that `parse_sexpr_stream` will return exactly the same item.
+///
+/// Text is escaped as necessary. Fails if an atom or node name is empty or contains a delimiter,
+/// or a list starts with an atom, since those can't be represented. Items containing lists can
+/// only be parsed back with `ParserOptions::with_lists`.
+pub fn to_sexpr_string<S: AsSExpr>(item: &S) -> Result<String, SExprError> {
+    validate_atoms(item)?;
+    let mut out = String::new();
+    write_item(&mut out, item).map_err(|_| SExprError::Io {
+        source: std::io::Error::other("formatting failed"),
+        position: out.len(),
+    })?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use proptest::prelude::*;
+
+    fn arb_item() -> impl Strategy<Value = SExprItem> {
+        let atom = "[^\\s()\"]{1,8}";
+        let leaf = prop_oneof![
+            atom.prop_map(SExprItem::Atom),
+            any::<String>().prop_map(SExprItem::Text),
+        ];
+        leaf.prop_recursive(4, 64, 6, move |inner| {
+            (atom, prop::collection::vec(inner, 0..6))
+                .prop_map(|(name, items)| SExprItem::Node(name, items))
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_display_round_trips(item in arb_item()) {
+            let written = item.to_string();
+            prop_assert_eq!(to_sexpr_string(&item).unwrap(), written.clone());
+            prop_assert_eq!(parse_sexpr_stream(&written).unwrap(), vec![item]);
+        }
+    }
+
+    #[test]
+    fn test_text_is_escaped() {
+        let item = SExprItem::Node(
+            "mixop".to_string(),
+            vec![SExprItem::Text(r#"%"%\"#.to_string())],
+        );
+        assert_eq!(item.to_string(), r#"(mixop "%\"%\\")"#);
+        assert_eq!(parse_sexpr_stream(&item.to_string()).unwrap(), vec![item]);
+    }
+
+    #[test]
+    fn test_text_is_escaped_like_ocaml() {
+        let item = SExprItem::Text("tab\there\nnew\r\u{8}\u{0}'é".to_string());
+        assert_eq!(item.to_string(), r#""tab\there\nnew\r\b\000'\195\169""#);
+        assert_eq!(parse_sexpr_stream(&item.to_string()).unwrap(), vec![item]);
+    }
+
+    #[test]
+    fn test_lists_round_trip() {
+        let options = ParserOptions::new().with_lists(true);
+        for input in ["()", "(() (a) ((b) c) (\"d\" e))"] {
+            let items = options.parse(input).unwrap();
+            assert_eq!(to_sexpr_string(&items[0]).unwrap(), input);
+        }
+        let item = SExprItem::List(vec![SExprItem::Atom("a".to_string())]);
+        assert!(matches!(
+            to_sexpr_string(&item),
+            Err(SExprError::InvalidList { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_atoms() {
+        for atom in ["", "a b", "a(", ")", "\"a"] {
+            let item = SExprItem::Node("n".to_string(), vec![SExprItem::Atom(atom.to_string())]);
+            assert!(
+                matches!(to_sexpr_string(&item), Err(SExprError::InvalidAtom { .. })),
+                "expected atom to be rejected: {atom:?}"
+            );
+            let item = SExprItem::Node(atom.to_string(), vec![]);
+            assert!(to_sexpr_string(&item).is_err());
+        }
+    }
+}
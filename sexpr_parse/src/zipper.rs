@@ -0,0 +1,381 @@
+use crate::sexpr::SExprItem;
+
+/// A cursor for editing a forest of S-expressions in place.
+///
+/// The zipper owns the forest and keeps a focus on one item, identified by its path as returned by
+/// `Selector::select`: the index of its top-level item, followed by the index of each child within
+/// its parent node. Navigation methods return whether the focus moved, leaving it where it was if
+/// not. Call `into_forest` to get the edited forest back.
+///
+/// The focus is only missing when the forest is empty, for example after deleting its last item.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SExprZipper {
+    forest: Vec<SExprItem>,
+    path: Vec<usize>,
+}
+
+/// Follows a path of child indices from a list of items, returning the children of the node it
+/// leads to.
+fn children_at<'a>(items: &'a [SExprItem], path: &[usize]) -> Option<&'a [SExprItem]> {
+    path.iter()
+        .try_fold(items, |items, &index| match items.get(index)? {
+            SExprItem::Node(_, children) | SExprItem::List(children) => Some(children.as_slice()),
+            SExprItem::Atom(_) | SExprItem::Text(_) => None,
+        })
+}
+
+fn children_at_mut<'a>(
+    items: &'a mut Vec<SExprItem>,
+    path: &[usize],
+) -> Option<&'a mut Vec<SExprItem>> {
+    path.iter()
+        .try_fold(items, |items, &index| match items.get_mut(index)? {
+            SExprItem::Node(_, children) | SExprItem::List(children) => Some(children),
+            SExprItem::Atom(_) | SExprItem::Text(_) => None,
+        })
+}
+
+impl SExprZipper {
+    /// Creates a zipper focused on the first top-level item of the forest.
+    #[must_use]
+    pub fn new(forest: Vec<SExprItem>) -> Self {
+        Self {
+            forest,
+            path: vec![0],
+        }
+    }
+
+    /// Returns the edited forest.
+    #[must_use]
+    pub fn into_forest(self) -> Vec<SExprItem> {
+        self.forest
+    }
+
+    #[must_use]
+    pub fn forest(&self) -> &[SExprItem] {
+        &self.forest
+    }
+
+    /// The path of the focused item.
+    #[must_use]
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// How many nodes contain the focused item, which is 0 for top-level items.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+
+    fn index(&self) -> usize {
+        self.path.last().copied().unwrap_or_default()
+    }
+
+    /// The focused item and its siblings, in order.
+    fn siblings(&self) -> &[SExprItem] {
+        let parent = self.path.split_last().map_or(&[][..], |(_, parent)| parent);
+        children_at(&self.forest, parent).unwrap_or_default()
+    }
+
+    fn siblings_mut(&mut self) -> Option<&mut Vec<SExprItem>> {
+        let (_, parent) = self.path.split_last()?;
+        children_at_mut(&mut self.forest, parent)
+    }
+
+    #[must_use]
+    pub fn focus(&self) -> Option<&SExprItem> {
+        self.siblings().get(self.index())
+    }
+
+    pub fn focus_mut(&mut self) -> Option<&mut SExprItem> {
+        let index = self.index();
+        self.siblings_mut()?.get_mut(index)
+    }
+
+    /// Moves the focus to the item at the given path, if there is one.
+    pub fn goto(&mut self, path: &[usize]) -> bool {
+        let Some((&index, parent)) = path.split_last() else {
+            return false;
+        };
+        let exists = children_at(&self.forest, parent).is_some_and(|items| index < items.len());
+        if exists {
+            self.path = path.to_vec();
+        }
+        exists
+    }
+
+    /// Moves the focus to the node containing it.
+    pub fn parent(&mut self) -> bool {
+        if self.path.len() <= 1 {
+            return false;
+        }
+        self.path.pop();
+        true
+    }
+
+    /// Moves the focus to a child of the focused node or list.
+    pub fn child(&mut self, index: usize) -> bool {
+        let exists = match self.focus() {
+            Some(SExprItem::Node(_, children) | SExprItem::List(children)) => {
+                index < children.len()
+            }
+            _ => false,
+        };
+        if exists {
+            self.path.push(index);
+        }
+        exists
+    }
+
+    pub fn next_sibling(&mut self) -> bool {
+        let Some(next) = self.index().checked_add(1) else {
+            return false;
+        };
+        self.move_to_sibling(next)
+    }
+
+    pub fn prev_sibling(&mut self) -> bool {
+        let Some(prev) = self.index().checked_sub(1) else {
+            return false;
+        };
+        self.move_to_sibling(prev)
+    }
+
+    fn move_to_sibling(&mut self, index: usize) -> bool {
+        let exists = index < self.siblings().len();
+        if exists && let Some(last) = self.path.last_mut() {
+            *last = index;
+        }
+        exists
+    }
+
+    /// Moves the focus to the next item in the order they are written: the first child of the
+    /// focused node, or else the next sibling of the focus or of the nearest node containing it.
+    ///
+    /// Starting from the first top-level item and calling this until it returns `false` visits
+    /// every item in the forest.
+    pub fn advance(&mut self) -> bool {
+        if self.child(0) {
+            return true;
+        }
+        let start = self.path.clone();
+        loop {
+            if self.next_sibling() {
+                return true;
+            }
+            if !self.parent() {
+                self.path = start;
+                return false;
+            }
+        }
+    }
+
+    /// Replaces the focused item, returning the item it replaced.
+    pub fn replace(&mut self, item: SExprItem) -> Option<SExprItem> {
+        self.focus_mut().map(|focus| std::mem::replace(focus, item))
+    }
+
+    /// Inserts an item before the focus, keeping the focus on the same item. If the forest is
+    /// empty, the item is inserted and focused instead.
+    pub fn insert_before(&mut self, item: SExprItem) {
+        let index = self.index();
+        let Some(siblings) = self.siblings_mut() else {
+            return;
+        };
+        let was_empty = siblings.is_empty();
+        siblings.insert(index.min(siblings.len()), item);
+        if !was_empty && let Some(last) = self.path.last_mut() {
+            *last = index.saturating_add(1);
+        }
+    }
+
+    /// Inserts an item after the focus, keeping the focus on the same item. If the forest is empty,
+    /// the item is inserted and focused instead.
+    pub fn insert_after(&mut self, item: SExprItem) {
+        let index = self.index();
+        let Some(siblings) = self.siblings_mut() else {
+            return;
+        };
+        let at = if siblings.is_empty() {
+            0
+        } else {
+            index.saturating_add(1)
+        };
+        siblings.insert(at.min(siblings.len()), item);
+    }
+
+    /// Inserts an item among the children of the focused node or list, at the given index or at the
+    /// end if the index is past it. Returns `false`, leaving the forest unchanged, if the focus is
+    /// an atom or text.
+    pub fn insert_child(&mut self, index: usize, item: SExprItem) -> bool {
+        match self.focus_mut() {
+            Some(SExprItem::Node(_, children) | SExprItem::List(children)) => {
+                children.insert(index.min(children.len()), item);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the focused item and returns it.
+    ///
+    /// The focus moves to the next sibling, or the previous one if the item was the last of its
+    /// siblings, or otherwise to the node that contained it.
+    pub fn delete(&mut self) -> Option<SExprItem> {
+        let index = self.index();
+        let siblings = self.siblings_mut()?;
+        if index >= siblings.len() {
+            return None;
+        }
+        let removed = siblings.remove(index);
+        let remaining = siblings.len();
+        if index >= remaining
+            && !(remaining == 0 && self.parent())
+            && let Some(last) = self.path.last_mut()
+        {
+            *last = index.saturating_sub(1);
+        }
+        Some(removed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    const INPUT: &str = r#"
+(module (func "f" (param i32) (result i32)) (func "g" (local i64) (local i32) (nop)))
+(export "f")
+"#;
+
+    fn zipper() -> SExprZipper {
+        SExprZipper::new(parse_sexpr_stream(INPUT).unwrap())
+    }
+
+    fn forest_string(zipper: SExprZipper) -> String {
+        zipper
+            .into_forest()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_zipper_navigation() {
+        let mut zipper = zipper();
+        assert!(!zipper.parent());
+        assert!(!zipper.prev_sibling());
+        assert!(zipper.child(1) && zipper.child(3));
+        assert_eq!(zipper.path(), [0, 1, 3]);
+        assert_eq!(zipper.depth(), 2);
+        assert_eq!(zipper.focus().unwrap().to_string(), "(nop)");
+        assert!(!zipper.next_sibling());
+        assert!(zipper.prev_sibling());
+        assert!(zipper.child(0));
+        assert!(!zipper.child(0));
+        assert_eq!(zipper.focus(), Some(&SExprItem::Atom("i32".to_string())));
+        assert!(zipper.parent() && zipper.parent());
+        assert_eq!(zipper.path(), [0, 1]);
+        assert!(!zipper.goto(&[1, 1]));
+        assert_eq!(zipper.path(), [0, 1]);
+        assert!(zipper.goto(&[0, 0, 1]));
+        assert_eq!(zipper.focus().unwrap().to_string(), "(param i32)");
+
+        let mut zipper = self::zipper();
+        let mut visited = 1;
+        while zipper.advance() {
+            visited += 1;
+        }
+        assert_eq!(visited, 16);
+        assert_eq!(zipper.path(), [1, 0]);
+    }
+
+    #[test]
+    fn test_zipper_rename_everywhere() {
+        let mut zipper = zipper();
+        loop {
+            if let Some(SExprItem::Atom(atom)) = zipper.focus_mut()
+                && atom == "i32"
+            {
+                *atom = "v128".to_string();
+            }
+            if !zipper.advance() {
+                break;
+            }
+        }
+        assert_eq!(
+            forest_string(zipper),
+            [
+                concat!(
+                    r#"(module (func "f" (param v128) (result v128)) "#,
+                    r#"(func "g" (local i64) (local v128) (nop)))"#
+                ),
+                r#"(export "f")"#,
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_zipper_edits() {
+        let local =
+            |ty: &str| SExprItem::Node("local".to_string(), vec![SExprItem::Atom(ty.into())]);
+        let mut zipper = zipper();
+        assert!(zipper.goto(&[0, 1, 1]));
+        // Strip everything in "g" after its first local
+        while zipper.next_sibling() {}
+        while zipper.focus() != Some(&local("i64")) {
+            zipper.delete().unwrap();
+        }
+        assert_eq!(zipper.path(), [0, 1, 1]);
+        zipper.insert_after(local("f32"));
+        zipper.insert_before(SExprItem::Node(
+            "param".to_string(),
+            vec![SExprItem::Atom("i32".to_string())],
+        ));
+        assert_eq!(zipper.path(), [0, 1, 2]);
+        assert!(zipper.parent());
+        assert!(zipper.insert_child(usize::MAX, SExprItem::Node("return".to_string(), vec![])));
+        assert!(zipper.goto(&[1, 0]));
+        assert!(!zipper.insert_child(0, SExprItem::Atom("x".to_string())));
+        assert_eq!(
+            zipper.replace(SExprItem::Text("g".to_string())),
+            Some(SExprItem::Text("f".to_string()))
+        );
+        assert_eq!(
+            forest_string(zipper),
+            [
+                concat!(
+                    r#"(module (func "f" (param i32) (result i32)) "#,
+                    r#"(func "g" (param i32) (local i64) (local f32) (return)))"#
+                ),
+                r#"(export "g")"#,
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_zipper_delete_moves_focus() {
+        let mut zipper = SExprZipper::new(parse_sexpr_stream("(a (b) (c)) d").unwrap());
+        assert!(zipper.goto(&[0, 0]));
+        assert_eq!(
+            zipper.delete(),
+            Some(SExprItem::Node("b".to_string(), vec![]))
+        );
+        assert_eq!(zipper.path(), [0, 0]);
+        zipper.delete();
+        assert_eq!(zipper.path(), [0]);
+        assert!(zipper.next_sibling());
+        zipper.delete();
+        assert_eq!(zipper.path(), [0]);
+        zipper.delete();
+        assert_eq!(zipper.focus(), None);
+        assert_eq!(zipper.delete(), None);
+        zipper.insert_after(SExprItem::Atom("e".to_string()));
+        assert_eq!(zipper.focus(), Some(&SExprItem::Atom("e".to_string())));
+        assert_eq!(forest_string(zipper), "e");
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +7,12 @@ pub enum Error {
     SExpr(#[from] sexpr_parse::SExprError),
     #[error("Error decoding SpecTec AST: {0}")]
     Decode(#[from] decode::Error),
+    #[error("{}: {source}", path.display())]
+    InFile {
+        path: PathBuf,
+        #[source]
+        source: Box<Error>,
+    },
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -1,9 +1,9 @@
 use crate::{SpecTecArg, SpecTecExp, SpecTecIter, SpecTecIterExp};
-use decode_derive::SExprDecode;
+use decode_derive::{SExprDecode, SExprEncode};
 
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#L149>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecSym {
     #[sexpr_node(name = "var")]
     Var { x: String, as1: Vec<SpecTecArg> },
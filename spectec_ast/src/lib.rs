@@ -31,22 +31,118 @@ pub use crate::{
 /// Will return an error if any of the S-expressions cannot be decoded, or if the S-expressions are
 /// not a valid SpecTec AST stream.
 pub fn parse_spectec_stream(input: &str) -> crate::Result<Vec<SpecTecDef>> {
-    let sexpr_items = sexpr_parse::parse_sexpr_stream(input)?;
+    // The decoded AST owns its data, so the S-expressions can borrow from the input
+    decode_spectec_items(&sexpr_parse::parse_sexpr_stream_borrowed(input)?)
+}
+
+/// Parses a SpecTec AST stream from the file at the given path, mapping it into memory rather than
+/// reading it into a buffer.
+///
+/// # Errors
+///
+/// Will return an error, wrapped in `Error::InFile` with the path, if the file cannot be read or
+/// is not UTF-8, or for any of the reasons `parse_spectec_stream` would.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated until this returns, as for
+/// `sexpr_parse::MappedFile::open`.
+pub unsafe fn parse_spectec_file<P: AsRef<std::path::Path>>(
+    path: P,
+) -> crate::Result<Vec<SpecTecDef>> {
+    let path = path.as_ref();
+    // SAFETY: The caller guarantees that the file isn't changed until this returns, and the
+    // mapping is dropped before then.
+    unsafe { sexpr_parse::MappedFile::open(path) }
+        .map_err(crate::Error::from)
+        .and_then(|file| parse_spectec_stream(file.as_str()))
+        .map_err(|err| crate::Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        })
+}
+
+/// Decodes a SpecTec AST stream from S-expressions in the binary format written by
+/// `sexpr_parse::encode_sexpr_binary`, which is quicker to read than the text form.
+///
+/// # Errors
+///
+/// Will return an error if the binary input is malformed, or if the S-expressions are not a valid
+/// SpecTec AST stream.
+pub fn parse_spectec_binary(bytes: &[u8]) -> crate::Result<Vec<SpecTecDef>> {
+    decode_spectec_items(&sexpr_parse::decode_sexpr_binary_borrowed(bytes)?)
+}
+
+fn decode_spectec_items(
+    sexpr_items: &[sexpr_parse::SExprRef<'_>],
+) -> crate::Result<Vec<SpecTecDef>> {
     let mut items = sexpr_items.iter().peekable();
     let parsed = decode::Decode::decode(&mut items).map_err(crate::Error::from)?;
     // Ensure we consumed all the available items
     if let Some(item) = items.peek() {
         return Err(crate::Error::from(decode::Error::unparsed_sexpr::<
             Vec<SpecTecDef>,
-        >(item)));
+            _,
+        >(*item)));
+    }
+    Ok(parsed)
+}
+
+/// Parses a SpecTec AST stream like `parse_spectec_stream`, parsing and decoding chunks of
+/// definitions on separate threads.
+///
+/// The result is identical to `parse_spectec_stream`: a malformed S-expression anywhere in the
+/// input is reported ahead of any definition that fails to decode.
+pub fn parse_spectec_stream_parallel(input: &str) -> crate::Result<Vec<SpecTecDef>> {
+    let options = sexpr_parse::ParserOptions::default();
+    let chunks = sexpr_parse::parse_sexpr_stream_parallel_map(input, &options, |items| {
+        let mut defs = Vec::with_capacity(items.len());
+        for item in &items {
+            // As when decoding the whole stream as a `Vec`, decoding stops at the first item that
+            // isn't a definition
+            let Some(def) = decode::Decode::probe_one(item) else {
+                let err = decode::Error::unparsed_sexpr::<Vec<SpecTecDef>, _>(item);
+                return (defs, Some(err));
+            };
+            defs.push(def);
+        }
+        (defs, None)
+    })?;
+    let mut parsed = Vec::new();
+    for (defs, err) in chunks {
+        parsed.extend(defs);
+        if let Some(err) = err {
+            return Err(crate::Error::from(err));
+        }
     }
     Ok(parsed)
 }
 
+/// Lazily parses a SpecTec AST stream from the input string, decoding each definition as soon as
+/// its S-expression has been parsed.
+///
+/// Iteration ends after the first error, so the remainder of the input is never parsed if the
+/// iterator is abandoned early or an error is encountered.
+pub fn parse_spectec_iter(input: &str) -> impl Iterator<Item = crate::Result<SpecTecDef>> + '_ {
+    sexpr_parse::parse_sexpr_iter(input)
+        .map(|item| {
+            let item = item?;
+            decode::Decode::decode(&mut std::iter::once(&item).peekable())
+                .map_err(crate::Error::from)
+        })
+        .scan(false, |failed, def| {
+            if *failed {
+                return None;
+            }
+            *failed = def.is_err();
+            Some(def)
+        })
+}
+
 #[cfg(test)]
 mod test {
     use crate::*;
-    use decode_derive::SExprDecode;
+    use decode_derive::{SExprDecode, SExprEncode};
     use sexpr_parse::parse_sexpr_stream;
 
     #[test]
@@ -268,6 +364,77 @@ mod test {
         assert_eq!(parsed, vec![TestEnum::A(0)]);
     }
 
+    #[test]
+    fn test_spectec_encode_round_trips() {
+        #[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
+        pub enum TestEnum {
+            #[sexpr_node(name = "a")]
+            A {
+                b: Vec<TestEnum2>,
+                c: Option<String>,
+                d: Box<TestEnum>,
+            },
+            #[sexpr_node(name = "e")]
+            E(u64, i64),
+            #[sexpr_node(name = "f")]
+            F,
+        }
+        #[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
+        pub enum TestEnum2 {
+            #[sexpr_atom(name = "g")]
+            G,
+            #[sexpr_atom()]
+            H(bool),
+        }
+
+        let input = r#"(a g true (a "x" (e 1 -2))) (f)"#;
+        let sexprs = parse_sexpr_stream(input).unwrap();
+        let parsed: Vec<TestEnum> = decode::Decode::decode(&mut sexprs.iter().peekable()).unwrap();
+        assert_eq!(decode::Encode::to_sexpr_items(&parsed), sexprs);
+        assert_eq!(
+            parsed[0],
+            TestEnum::A {
+                b: vec![TestEnum2::G, TestEnum2::H(true)],
+                c: None,
+                d: Box::new(TestEnum::A {
+                    b: vec![],
+                    c: Some("x".to_owned()),
+                    d: Box::new(TestEnum::E(1, -2)),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_spectec_node_numeric_atoms() {
+        #[derive(SExprDecode, Clone, Debug, PartialEq)]
+        pub enum TestEnum {
+            #[sexpr_node(name = "a")]
+            A(u64, i64, i64, bool),
+        }
+
+        let input = r"(a 0xFD -3 +0x7f true)";
+        let sexprs = parse_sexpr_stream(input).unwrap();
+        let parsed: decode::Result<TestEnum> =
+            decode::Decode::decode(&mut sexprs.iter().peekable());
+        assert!(parsed.is_err(), "signed hex is not an integer");
+
+        let input = r"(a 0xFD -3 0x7f true)";
+        let sexprs = parse_sexpr_stream(input).unwrap();
+        let parsed: TestEnum = decode::Decode::decode(&mut sexprs.iter().peekable()).unwrap();
+        assert_eq!(parsed, TestEnum::A(0xFD, -3, 0x7F, true));
+
+        let input = r"(a -1 0 0 true)";
+        let sexprs = parse_sexpr_stream(input).unwrap();
+        let parsed: decode::Result<TestEnum> =
+            decode::Decode::decode(&mut sexprs.iter().peekable());
+        assert!(matches!(
+            parsed.unwrap_err().kind,
+            decode::ErrorKind::Wrapped(err)
+                if matches!(err.kind, decode::ErrorKind::CannotDecodeSExpr(_))
+        ));
+    }
+
     #[test]
     fn test_spectec_node_option_named_field() {
         #[derive(SExprDecode, Clone, Debug, PartialEq)]
@@ -387,6 +554,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_parse_spectec_iter() {
+        let input = r#"
+(typ "m" (inst (alias nat)))
+(typ "n" (inst (alias nat)))
+(bad
+"#;
+        let mut defs = parse_spectec_iter(input);
+        assert_eq!(
+            defs.next().unwrap().unwrap(),
+            parse_spectec_stream(r#"(typ "m" (inst (alias nat)))"#).unwrap()[0]
+        );
+        assert!(defs.next().unwrap().is_ok());
+        assert!(matches!(defs.next(), Some(Err(crate::Error::SExpr(_)))));
+        assert!(defs.next().is_none());
+    }
+
     #[test]
     fn test_parse_spectec_stream_rejects_trailing_invalid_item() {
         let input = r#"
@@ -400,4 +584,71 @@ mod test {
             "Error decoding SpecTec AST: Error decoding alloc::vec::Vec<spectec_ast::definitions::SpecTecDef>: Extra unparsed S-expression remaining: Node(\"bad\", [])"
         );
     }
+
+    #[test]
+    fn test_decode_interned_items() {
+        let input = r#"
+(typ "m" (inst (alias nat)))
+(typ "n" (inst (alias (var "m"))))
+"#;
+        let mut interner = sexpr_parse::Interner::new();
+        let sexprs = sexpr_parse::parse_sexpr_stream_interned(input, &mut interner).unwrap();
+        let parsed: Vec<SpecTecDef> =
+            decode::Decode::decode(&mut sexprs.iter().peekable()).unwrap();
+        assert_eq!(parsed, parse_spectec_stream(input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_spectec_file() {
+        // Only this test uses the file, and it is only written between parses
+        let path = std::env::temp_dir().join(format!("spectec_ast_{}", std::process::id()));
+        std::fs::write(&path, "(typ \"m\" (inst (alias nat)))\n(bad)\n").unwrap();
+        let err = unsafe { parse_spectec_file(&path) }.unwrap_err();
+        let Error::InFile {
+            path: in_path,
+            source,
+        } = &err
+        else {
+            panic!("expected path: {err:?}");
+        };
+        assert_eq!(in_path, &path);
+        assert!(matches!(**source, Error::Decode(_)), "{err}");
+        assert!(err.to_string().starts_with(&path.display().to_string()));
+
+        std::fs::write(&path, "(typ \"m\" (inst (alias nat)))\n").unwrap();
+        assert_eq!(unsafe { parse_spectec_file(&path) }.unwrap().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            unsafe { parse_spectec_file(&path) },
+            Err(Error::InFile { source, .. }) if matches!(*source, Error::SExpr(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_spectec_stream_parallel() {
+        let valid = r#"
+(typ "m" (inst (alias nat)))
+(typ "n" (inst (alias nat)))
+(typ "o" (inst (alias (var "m"))))
+"#;
+        let inputs = [
+            valid.to_string(),
+            format!(
+                "{valid}(bad)
+{valid}"
+            ),
+            format!(
+                "{valid}(bad)
+{valid}(typ\"x\")"
+            ),
+            format!("{valid}(typ \"unterminated"),
+        ];
+        for input in &inputs {
+            assert_eq!(
+                format!("{:?}", parse_spectec_stream_parallel(input)),
+                format!("{:?}", parse_spectec_stream(input)),
+                "{input}"
+            );
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use decode_derive::SExprDecode;
+use decode_derive::{SExprDecode, SExprEncode};
 
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#L14>
 #[derive(Clone, Debug, PartialEq)]
@@ -22,22 +22,28 @@ impl MixOp {
 }
 
 impl decode::Decode for MixOp {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> decode::Result<Self> {
-        match items.next() {
-            Some(sexpr_parse::SExprItem::Text(t)) => {
+        match items.next().map(|item| (item, item.view())) {
+            Some((_, sexpr_parse::SExprView::Text(t))) => {
                 Ok(MixOp(t.split('%').map(str::to_owned).collect()))
             }
-            Some(item) => Err(decode::Error::cannot_decode_sexpr::<Self>(item)),
+            Some((item, _)) => Err(decode::Error::cannot_decode_sexpr::<Self, _>(item)),
             None => Err(decode::Error::required_missing_sexpr::<Self>()),
         }
     }
 }
 
+impl decode::Encode for MixOp {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        items.push(sexpr_parse::SExprItem::Text(self.0.join("%")));
+    }
+}
+
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#19>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecNum {
     #[sexpr_node(name = "nat")]
     Nat(u64),
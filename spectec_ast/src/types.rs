@@ -1,9 +1,9 @@
 use crate::{SpecTecArg, SpecTecBoolTyp, SpecTecIter, SpecTecNumTyp, SpecTecPrem};
-use decode_derive::SExprDecode;
+use decode_derive::{SExprDecode, SExprEncode};
 
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#L70>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecOpTyp {
     #[sexpr_atom()]
     Num(SpecTecNumTyp),
@@ -13,7 +13,7 @@ pub enum SpecTecOpTyp {
 
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#74>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecTyp {
     #[sexpr_node(name = "var")]
     Var { x: String, as1: Vec<SpecTecArg> },
@@ -34,7 +34,7 @@ pub enum SpecTecTyp {
 
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#83>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecDefTyp {
     #[sexpr_node(name = "alias")]
     Alias { typ: SpecTecTyp },
@@ -46,7 +46,7 @@ pub enum SpecTecDefTyp {
 
 /// <https://github.com/WebAssembly/spec/blob/9479f1d0760494a93fcc73f7cf94c211ac91eec7/spectec/src/backend-ast/print.ml#89>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecTypBind {
     #[sexpr_node(name = "bind")]
     Bind { id: String, typ: SpecTecTyp },
@@ -54,7 +54,7 @@ pub enum SpecTecTypBind {
 
 /// <https://github.com/WebAssembly/spec/blob/d7b678327cd370cdbc5acfa94bd108772e2bef68/spectec/src/backend-ast/print.ml#L89>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecTypField {
     #[sexpr_node(name = "field")]
     Field {
@@ -67,7 +67,7 @@ pub enum SpecTecTypField {
 
 /// <https://github.com/WebAssembly/spec/blob/d7b678327cd370cdbc5acfa94bd108772e2bef68/spectec/src/backend-ast/print.ml#L92>
 #[allow(unused)]
-#[derive(SExprDecode, Clone, Debug, PartialEq)]
+#[derive(SExprDecode, SExprEncode, Clone, Debug, PartialEq)]
 pub enum SpecTecTypCase {
     #[sexpr_node(name = "case")]
     Field {
@@ -1,13 +1,17 @@
 impl crate::Decode for bool {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self> {
-        match items.next() {
-            Some(sexpr_parse::SExprItem::Atom(t)) => {
-                t.parse().map_err(crate::Error::parse_bool_err::<Self>)
-            }
-            Some(item) => Err(crate::Error::cannot_decode_sexpr::<Self>(item)),
+        match items.next().map(|item| (item, item.atom_kind())) {
+            Some((_, Some(sexpr_parse::AtomKind::Bool(b)))) => Ok(b),
+            Some((item, _)) => Err(crate::Error::cannot_decode_sexpr::<Self, _>(item)),
             None => Err(crate::Error::required_missing_sexpr::<Self>()),
         }
     }
 }
+
+impl crate::Encode for bool {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        items.push(sexpr_parse::SExprItem::Atom(self.to_string()));
+    }
+}
@@ -1,5 +1,5 @@
 impl<T: crate::Decode> crate::Decode for Box<T> {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self> {
         T::decode(items)
@@ -7,3 +7,9 @@ impl<T: crate::Decode> crate::Decode for Box<T> {
             .map_err(crate::Error::wrapped::<Self>)
     }
 }
+
+impl<T: crate::Encode> crate::Encode for Box<T> {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        T::encode(self, items);
+    }
+}
@@ -1,11 +1,15 @@
 pub trait Decode: Sized {
     /// Consumes zero or more S-expression items from the iterator to construct `Self`.
     ///
+    /// Items may be in any representation implementing `AsSExpr`, such as `SExprItem` or the
+    /// borrowed `SExprRef`. Numbers and booleans are decoded through `AsSExpr::atom_kind`, which
+    /// `SExprRef` stores from parsing but `SExprItem` works out again each time.
+    ///
     /// # Errors
     ///
     /// Will return an error if the S-expression cannot be represented by `Self`. To avoid this case,
     /// use `can_decode` to check if the item can be decoded first.
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self>;
 
@@ -17,7 +21,7 @@ pub trait Decode: Sized {
     /// consuming input, which must not be interpreted as presence by outer greedy
     /// decoders.
     #[must_use]
-    fn probe_one(item: &sexpr_parse::SExprItem) -> Option<Self> {
+    fn probe_one<S: sexpr_parse::AsSExpr>(item: &S) -> Option<Self> {
         let mut probe = std::iter::once(item).peekable();
         let out = Self::decode(&mut probe).ok()?;
 
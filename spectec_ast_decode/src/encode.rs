@@ -0,0 +1,108 @@
+pub trait Encode {
+    /// Appends the S-expression items representing `self`, the inverse of `Decode::decode`.
+    ///
+    /// Integers are always written in decimal, so an item decoded from a hex atom such as `0xFF`
+    /// is written differently, though it decodes to the same value.
+    ///
+    /// Nothing marks where a value inside an `Option` or `Vec` starts or ends, since decoding
+    /// them tries each item as a single value. Decoding gives back an equal value only if every
+    /// value inside an `Option` or `Vec` encodes to exactly one item. Otherwise:
+    ///
+    /// - `None` encodes to no items, so `vec![Some(1), None, Some(2)]` decodes as
+    ///   `vec![Some(1), Some(2)]`, and `Some(None)` decodes as `None`.
+    /// - An inner `Vec` encodes to one item per element, so `vec![vec![1, 2]]` decodes as
+    ///   `vec![vec![1], vec![2]]`, and `Some(vec![1, 2])` decodes as `Some(vec![1])` with an
+    ///   item left over.
+    /// - An empty inner `Vec` encodes to no items, so `vec![vec![1], vec![]]` decodes as
+    ///   `vec![vec![1]]`, and `Some(vec![])` decodes as `None`.
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>);
+
+    /// Returns the S-expression items representing `self`.
+    #[must_use]
+    fn to_sexpr_items(&self) -> Vec<sexpr_parse::SExprItem> {
+        let mut items = Vec::new();
+        self.encode(&mut items);
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Decode, Encode};
+    use sexpr_parse::SExprItem;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Encode + Decode + PartialEq + Debug>(value: &T) {
+        let items = value.to_sexpr_items();
+        let mut iter = items.iter().peekable();
+        assert_eq!(&T::decode(&mut iter).unwrap(), value, "{items:?}");
+        assert_eq!(iter.next(), None, "{items:?}");
+    }
+
+    #[test]
+    fn encoding_is_inverse_of_decoding() {
+        round_trip(&true);
+        round_trip(&false);
+        round_trip(&0u64);
+        round_trip(&u64::MAX);
+        round_trip(&i64::MIN);
+        round_trip(&-1i64);
+        round_trip(&String::new());
+        round_trip(&"say \"hi\"\n".to_owned());
+        round_trip(&Some(3u64));
+        round_trip(&None::<String>);
+        round_trip(&vec![Some(1u64), Some(2)]);
+        round_trip(&Box::new(vec![true, false]));
+    }
+
+    #[test]
+    fn decoded_items_encode_unchanged() {
+        let items = sexpr_parse::parse_sexpr_stream("\"x\" 12 -4 true false").unwrap();
+        let mut iter = items.iter().peekable();
+        let text = String::decode(&mut iter).unwrap();
+        let nat = u64::decode(&mut iter).unwrap();
+        let int = i64::decode(&mut iter).unwrap();
+        let bools = Vec::<bool>::decode(&mut iter).unwrap();
+
+        let mut encoded = Vec::new();
+        text.encode(&mut encoded);
+        nat.encode(&mut encoded);
+        int.encode(&mut encoded);
+        bools.encode(&mut encoded);
+        assert_eq!(encoded, items);
+
+        let hex = sexpr_parse::parse_sexpr_stream("0xFF").unwrap();
+        let value = u64::decode(&mut hex.iter().peekable()).unwrap();
+        assert_eq!(value.to_sexpr_items(), [SExprItem::Atom("255".to_owned())]);
+    }
+
+    /// Decodes the encoding of a value, returning what was decoded and how many items were left.
+    fn decode_encoded<T: Encode, U: Decode>(value: &T) -> (U, usize) {
+        let items = value.to_sexpr_items();
+        let mut iter = items.iter().peekable();
+        let decoded = U::decode(&mut iter).unwrap();
+        (decoded, iter.count())
+    }
+
+    #[test]
+    fn nested_options_and_vecs_are_ambiguous() {
+        assert_eq!(
+            decode_encoded(&vec![Some(1u64), None, Some(2)]),
+            (vec![Some(1u64), Some(2)], 0)
+        );
+        assert_eq!(decode_encoded(&Some(None::<u64>)), (None::<Option<u64>>, 0));
+        assert_eq!(
+            decode_encoded(&vec![vec![1u64, 2]]),
+            (vec![vec![1u64], vec![2]], 0)
+        );
+        assert_eq!(decode_encoded(&Some(vec![1u64, 2])), (Some(vec![1u64]), 1));
+        assert_eq!(
+            decode_encoded(&vec![vec![1u64], vec![]]),
+            (vec![vec![1u64]], 0)
+        );
+        assert_eq!(
+            decode_encoded(&Some(Vec::<u64>::new())),
+            (None::<Vec<u64>>, 0)
+        );
+    }
+}
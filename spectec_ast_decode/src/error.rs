@@ -14,8 +14,11 @@ pub enum ErrorKind {
     CannotDecodeSExpr(String),
     #[error("Extra unparsed S-expression remaining: {0}")]
     UnparsedSExpr(String),
+    #[deprecated(note = "booleans are decoded from `sexpr_parse::AtomKind`, which can't fail")]
     #[error("Error parsing a bool: {0}")]
     ParseBoolError(#[from] std::str::ParseBoolError),
+    #[deprecated(note = "integers are decoded from `sexpr_parse::AtomKind` and report \
+                         `CannotDecodeSExpr` if they don't fit")]
     #[error("Error parsing an int: {0}")]
     ParseIntError(#[from] std::num::ParseIntError),
     #[error("{0}")]
@@ -70,20 +73,25 @@ impl Error {
     }
 
     #[must_use]
-    pub fn cannot_decode_sexpr<T: crate::Decode>(sexpr: &sexpr_parse::SExprItem) -> Self {
+    pub fn cannot_decode_sexpr<T: crate::Decode, S: sexpr_parse::AsSExpr>(sexpr: &S) -> Self {
         Self::new::<T>(ErrorKind::CannotDecodeSExpr(format!("{sexpr:?}")))
     }
 
     #[must_use]
-    pub fn unparsed_sexpr<T: crate::Decode>(sexpr: &sexpr_parse::SExprItem) -> Self {
+    pub fn unparsed_sexpr<T: crate::Decode, S: sexpr_parse::AsSExpr>(sexpr: &S) -> Self {
         Self::new::<T>(ErrorKind::UnparsedSExpr(format!("{sexpr:?}")))
     }
 
+    #[deprecated(note = "booleans are decoded from `sexpr_parse::AtomKind`, which can't fail")]
+    #[allow(deprecated)]
     #[must_use]
     pub fn parse_bool_err<T: crate::Decode>(from: ParseBoolError) -> Self {
         Self::new::<T>(ErrorKind::from(from))
     }
 
+    #[deprecated(note = "integers are decoded from `sexpr_parse::AtomKind` and report \
+                         `CannotDecodeSExpr` if they don't fit")]
+    #[allow(deprecated)]
     #[must_use]
     pub fn parse_int_err<T: crate::Decode>(from: ParseIntError) -> Self {
         Self::new::<T>(ErrorKind::from(from))
@@ -11,6 +11,7 @@
 mod bool;
 mod r#box;
 mod decode;
+mod encode;
 mod error;
 mod i64;
 mod option;
@@ -20,5 +21,5 @@ mod vec;
 
 #[allow(unused)]
 pub use crate::{
-    bool::*, r#box::*, decode::*, error::*, i64::*, option::*, string::*, u64::*, vec::*,
+    bool::*, r#box::*, decode::*, encode::*, error::*, i64::*, option::*, string::*, u64::*, vec::*,
 };
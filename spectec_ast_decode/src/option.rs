@@ -1,9 +1,9 @@
 impl<T: crate::Decode> crate::Decode for Option<T> {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self> {
         if let Some(item) = items.peek()
-            && let Some(out) = T::probe_one(item)
+            && let Some(out) = T::probe_one(*item)
         {
             // We know that an item is available due to the success of the peek call
             #[allow(clippy::unwrap_used)]
@@ -14,10 +14,18 @@ impl<T: crate::Decode> crate::Decode for Option<T> {
     }
 }
 
+impl<T: crate::Encode> crate::Encode for Option<T> {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        if let Some(value) = self {
+            value.encode(items);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Decode;
-    use sexpr_parse::SExprItem;
+    use sexpr_parse::{SExprItem, SExprRef, classify_atom};
 
     #[test]
     fn nested_vec_probe_does_not_match_without_consuming() {
@@ -40,4 +48,20 @@ mod tests {
         assert_eq!(out, None);
         assert_eq!(iter.next(), Some(&SExprItem::Atom("x".to_owned())));
     }
+
+    #[test]
+    fn decodes_borrowed_items() {
+        let items = [
+            SExprRef::Atom("7", classify_atom("7")),
+            SExprRef::Text("x".into()),
+        ];
+        let mut iter = items.iter().peekable();
+
+        let out = Option::<u64>::decode(&mut iter).unwrap();
+        let text = Option::<String>::decode(&mut iter).unwrap();
+
+        assert_eq!(out, Some(7));
+        assert_eq!(text, Some("x".to_owned()));
+        assert_eq!(iter.next(), None);
+    }
 }
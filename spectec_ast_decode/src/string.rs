@@ -1,11 +1,17 @@
 impl crate::Decode for String {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self> {
-        match items.next() {
-            Some(sexpr_parse::SExprItem::Text(t)) => Ok(t.clone()),
-            Some(item) => Err(crate::Error::cannot_decode_sexpr::<Self>(item)),
+        match items.next().map(|item| (item, item.view())) {
+            Some((_, sexpr_parse::SExprView::Text(t))) => Ok(t.to_owned()),
+            Some((item, _)) => Err(crate::Error::cannot_decode_sexpr::<Self, _>(item)),
             None => Err(crate::Error::required_missing_sexpr::<Self>()),
         }
     }
 }
+
+impl crate::Encode for String {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        items.push(sexpr_parse::SExprItem::Text(self.clone()));
+    }
+}
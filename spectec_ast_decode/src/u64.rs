@@ -1,21 +1,20 @@
-fn parse_u64_str(s: &str) -> Result<u64, std::num::ParseIntError> {
-    if let Some(stripped) = s.strip_prefix("0x") {
-        u64::from_str_radix(stripped, 16)
-    } else {
-        s.parse::<u64>()
-    }
-}
-
 impl crate::Decode for u64 {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self> {
-        match items.next() {
-            Some(sexpr_parse::SExprItem::Atom(t)) => {
-                parse_u64_str(t).map_err(crate::Error::parse_int_err::<Self>)
-            }
-            Some(item) => Err(crate::Error::cannot_decode_sexpr::<Self>(item)),
-            None => Err(crate::Error::required_missing_sexpr::<Self>()),
-        }
+        let Some(item) = items.next() else {
+            return Err(crate::Error::required_missing_sexpr::<Self>());
+        };
+        // Integers may be written in decimal or hex
+        item.atom_kind()
+            .and_then(|kind| kind.as_integer())
+            .and_then(|n| Self::try_from(n).ok())
+            .ok_or_else(|| crate::Error::cannot_decode_sexpr::<Self, _>(item))
+    }
+}
+
+impl crate::Encode for u64 {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        items.push(sexpr_parse::SExprItem::Atom(self.to_string()));
     }
 }
@@ -1,10 +1,10 @@
 impl<T: crate::Decode> crate::Decode for Vec<T> {
-    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
         items: &mut std::iter::Peekable<I>,
     ) -> crate::Result<Self> {
         let mut parsed = Vec::new();
         while let Some(item) = items.peek() {
-            if let Some(out) = T::probe_one(item) {
+            if let Some(out) = T::probe_one(*item) {
                 // We know that an item is available due to the success of the peek call
                 #[allow(clippy::unwrap_used)]
                 items.next().unwrap();
@@ -16,3 +16,11 @@ impl<T: crate::Decode> crate::Decode for Vec<T> {
         Ok(parsed)
     }
 }
+
+impl<T: crate::Encode> crate::Encode for Vec<T> {
+    fn encode(&self, items: &mut Vec<sexpr_parse::SExprItem>) {
+        for item in self {
+            item.encode(items);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::utils::{atom_name, get_attr, node_name, syn_throw, syn_try};
+use proc_macro2::TokenStream;
+use quote::quote;
+use synstructure::{Structure, VariantInfo};
+
+fn encode_atom(item_attr: &syn::Attribute, v: &VariantInfo) -> Result<TokenStream, syn::Error> {
+    let pat = v.pat();
+    match (v.ast().fields, atom_name(item_attr)?) {
+        (syn::Fields::Unit, Some(item_name)) => Ok(quote!(
+            #pat => items.push(sexpr_parse::SExprItem::Atom(::std::string::String::from(#item_name))),
+        )),
+        (syn::Fields::Unit, None) => Err(syn::Error::new_spanned(
+            v.ast().ident,
+            "Unit atom variants must have a name specified",
+        )),
+        (syn::Fields::Unnamed(_), Some(item_name)) => Err(syn::Error::new_spanned(
+            item_name,
+            "Atom variants with unnamed fields must not have a name",
+        )),
+        (syn::Fields::Unnamed(unnamed), None) => match v.bindings() {
+            [binding] => Ok(quote!(
+                #pat => decode::Encode::encode(#binding, items),
+            )),
+            _ => Err(syn::Error::new_spanned(
+                unnamed,
+                "Atom variant with unnamed fields must only have one",
+            )),
+        },
+        (syn::Fields::Named(named), _) => Err(syn::Error::new_spanned(
+            named,
+            "Atoms cannot have named fields",
+        )),
+    }
+}
+
+fn encode_node(item_attr: &syn::Attribute, v: &VariantInfo) -> Result<TokenStream, syn::Error> {
+    let pat = v.pat();
+    let item_name = node_name(item_attr)?;
+    let bindings = v.bindings();
+    let children = if bindings.is_empty() {
+        quote!(::std::vec::Vec::new())
+    } else {
+        quote!({
+            let mut children = ::std::vec::Vec::new();
+            #( decode::Encode::encode(#bindings, &mut children); )*
+            children
+        })
+    };
+    Ok(quote!(
+        #pat => items.push(sexpr_parse::SExprItem::Node(
+            ::std::string::String::from(#item_name),
+            #children,
+        )),
+    ))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn sexpr_encode_derive(s: Structure) -> proc_macro2::TokenStream {
+    let encode = match s.ast().data {
+        syn::Data::Enum(_) => {
+            let mut arms = quote!();
+            for v in s.variants() {
+                let arm = if let Some(item_attr) = syn_try!(get_attr("sexpr_atom", v.ast().attrs)) {
+                    syn_try!(encode_atom(item_attr, v))
+                } else if let Some(item_attr) = syn_try!(get_attr("sexpr_node", v.ast().attrs)) {
+                    syn_try!(encode_node(item_attr, v))
+                } else {
+                    syn_throw!(syn::Error::new_spanned(
+                        v.ast().ident,
+                        "Must have either a sexpr_atom or sexpr_item attribute"
+                    ));
+                };
+                arms.extend(arm);
+            }
+
+            quote! {
+                gen impl decode::Encode for @Self {
+                    fn encode(&self, items: &mut ::std::vec::Vec<sexpr_parse::SExprItem>) {
+                        match *self {
+                            #arms
+                        }
+                    }
+                }
+            }
+        }
+        _ => {
+            syn_throw!(syn::Error::new_spanned(&s.ast().ident, "Unsupported data"))
+        }
+    };
+
+    s.gen_impl(encode)
+}
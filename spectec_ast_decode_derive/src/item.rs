@@ -1,4 +1,4 @@
-use crate::utils::{get_attr, syn_throw, syn_try};
+use crate::utils::{atom_name, get_attr, node_name, syn_throw, syn_try};
 use proc_macro2::TokenStream;
 use quote::{ToTokens, quote};
 use synstructure::Structure;
@@ -11,17 +11,7 @@ fn process_atom(
     variant_name: &syn::Ident,
     variant_fields: &syn::Fields,
 ) -> Result<(), syn::Error> {
-    let item_name: Option<syn::Expr> =
-        item_attr.parse_args_with(|parser: syn::parse::ParseStream| {
-            Ok(if parser.is_empty() {
-                None
-            } else {
-                syn::custom_keyword!(name);
-                parser.parse::<name>()?;
-                parser.parse::<syn::Token![=]>()?;
-                Some(parser.parse::<syn::Expr>()?)
-            })
-        })?;
+    let item_name = atom_name(item_attr)?;
     match variant_fields {
         syn::Fields::Unit => {
             if let Some(item_name) = item_name {
@@ -72,19 +62,14 @@ fn process_node(
     variant_fields: &syn::Fields,
 ) -> Result<(), syn::Error> {
     let variant_name_str = variant_name.to_token_stream().to_string();
-    let item_name: syn::Expr = item_attr.parse_args_with(|parser: syn::parse::ParseStream| {
-        syn::custom_keyword!(name);
-        parser.parse::<name>()?;
-        parser.parse::<syn::Token![=]>()?;
-        parser.parse::<syn::Expr>()
-    })?;
+    let item_name = node_name(item_attr)?;
     match variant_fields {
         syn::Fields::Unit => {
             (quote!(
                 #item_name => {
                     // There should be no items for a unit variant
                     if let Some(i) = items.into_iter().next() {
-                        return Err(decode::Error::unparsed_sexpr::<#s_name>(
+                        return Err(decode::Error::unparsed_sexpr::<#s_name, _>(
                             i,
                         ).with_variant(#variant_name_str));
                     }
@@ -119,7 +104,7 @@ fn process_node(
                     };
                     // We should have consumed all the items
                     if let Some(i) = items.next() {
-                        return Err(decode::Error::unparsed_sexpr::<#s_name>(
+                        return Err(decode::Error::unparsed_sexpr::<#s_name, _>(
                             i,
                         ).with_variant(#variant_name_str));
                     }
@@ -148,7 +133,7 @@ fn process_node(
                     );
                     // We should have consumed all the items
                     if let Some(i) = items.next() {
-                        return Err(decode::Error::unparsed_sexpr::<#s_name>(
+                        return Err(decode::Error::unparsed_sexpr::<#s_name, _>(
                             i,
                         ).with_variant(#variant_name_str));
                     }
@@ -226,17 +211,17 @@ pub(crate) fn sexpr_decode_derive(s: Structure) -> proc_macro2::TokenStream {
 
             quote! {
                 gen impl decode::Decode for @Self {
-                    fn decode<'a, I: Iterator<Item = &'a sexpr_parse::SExprItem>>(
+                    fn decode<'a, S: sexpr_parse::AsSExpr + 'a, I: Iterator<Item = &'a S>>(
                         items: &mut std::iter::Peekable<I>,
                     ) -> decode::Result<#s_name> {
-                        match items.next() {
-                            Some(sexpr_parse::SExprItem::Atom(name)) => match name.as_str() {
+                        match items.next().map(|item| (item, sexpr_parse::AsSExpr::view(item))) {
+                            Some((_, sexpr_parse::SExprView::Atom(name))) => match name {
                                 #atom_decoders
                             },
-                            Some(sexpr_parse::SExprItem::Node(name, items)) => match name.as_str() {
+                            Some((_, sexpr_parse::SExprView::Node(name, items))) => match name {
                                 #node_decoders
                             },
-                            Some(item) => return Err(decode::Error::cannot_decode_sexpr::<#s_name>(item)),
+                            Some((item, _)) => return Err(decode::Error::cannot_decode_sexpr::<#s_name, _>(item)),
                             None => return Err(decode::Error::required_missing_sexpr::<#s_name>()),
                         }
                     }
@@ -9,9 +9,11 @@
 
 extern crate proc_macro;
 
+mod encode;
 mod item;
 mod utils;
 
 use synstructure::decl_derive;
 
 decl_derive!([SExprDecode, attributes(sexpr_node, sexpr_atom)] => item::sexpr_decode_derive);
+decl_derive!([SExprEncode, attributes(sexpr_node, sexpr_atom)] => encode::sexpr_encode_derive);
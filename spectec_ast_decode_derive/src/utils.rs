@@ -35,3 +35,27 @@ pub(crate) fn get_attr<'a>(
         }
     }
 }
+
+/// Parses the arguments of a `sexpr_atom` attribute, which may give the name of the atom.
+pub(crate) fn atom_name(attr: &syn::Attribute) -> Result<Option<syn::Expr>, syn::Error> {
+    attr.parse_args_with(|parser: syn::parse::ParseStream| {
+        Ok(if parser.is_empty() {
+            None
+        } else {
+            syn::custom_keyword!(name);
+            parser.parse::<name>()?;
+            parser.parse::<syn::Token![=]>()?;
+            Some(parser.parse::<syn::Expr>()?)
+        })
+    })
+}
+
+/// Parses the arguments of a `sexpr_node` attribute, which must give the name of the node.
+pub(crate) fn node_name(attr: &syn::Attribute) -> Result<syn::Expr, syn::Error> {
+    attr.parse_args_with(|parser: syn::parse::ParseStream| {
+        syn::custom_keyword!(name);
+        parser.parse::<name>()?;
+        parser.parse::<syn::Token![=]>()?;
+        parser.parse::<syn::Expr>()
+    })
+}
@@ -0,0 +1,54 @@
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+const WASM_AST_STR: &str = include_str!("../src/wasm-3.0.spectec-ast");
+
+// Every result is unwrapped, so that a parser that fails early can't pass for a fast one
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wasm-3.0.spectec-ast");
+    group.throughput(Throughput::Bytes(WASM_AST_STR.len() as u64));
+    group.sample_size(20);
+    group.bench_function("parse_sexpr_stream", |b| {
+        b.iter(|| sexpr_parse::parse_sexpr_stream(black_box(WASM_AST_STR)).expect("parses"));
+    });
+    group.bench_function("parse_sexpr_stream_borrowed", |b| {
+        b.iter(|| {
+            sexpr_parse::parse_sexpr_stream_borrowed(black_box(WASM_AST_STR)).expect("parses")
+        });
+    });
+    group.bench_function("parse_sexpr_stream_interned", |b| {
+        b.iter(|| {
+            let mut interner = sexpr_parse::Interner::new();
+            sexpr_parse::parse_sexpr_stream_interned(black_box(WASM_AST_STR), &mut interner)
+                .expect("parses")
+        });
+    });
+    group.bench_function("parse_sexpr_events", |b| {
+        b.iter(|| {
+            sexpr_parse::parse_sexpr_events(black_box(WASM_AST_STR))
+                .try_fold(0_usize, |count, event| event.map(|_| count + 1))
+                .expect("parses")
+        });
+    });
+    let items = sexpr_parse::parse_sexpr_stream(WASM_AST_STR).expect("parses");
+    let encoded = sexpr_parse::encode_sexpr_binary(&items);
+    group.bench_function("decode_sexpr_binary_borrowed", |b| {
+        b.iter(|| sexpr_parse::decode_sexpr_binary_borrowed(black_box(&encoded)).expect("decodes"));
+    });
+    group.bench_function("parse_spectec_stream", |b| {
+        b.iter(|| spectec_ast::parse_spectec_stream(black_box(WASM_AST_STR)).expect("parses"));
+    });
+    group.bench_function("parse_spectec_stream_parallel", |b| {
+        b.iter(|| {
+            spectec_ast::parse_spectec_stream_parallel(black_box(WASM_AST_STR)).expect("parses")
+        });
+    });
+    group.bench_function("parse_spectec_binary", |b| {
+        b.iter(|| spectec_ast::parse_spectec_binary(black_box(&encoded)).expect("decodes"));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
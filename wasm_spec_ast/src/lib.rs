@@ -31,4 +31,49 @@ mod test {
         let ast = super::get_wasm_spectec_ast();
         assert!(!ast.is_empty());
     }
+
+    #[test]
+    fn test_parallel_parse_matches_sequential() {
+        let parallel = spectec_ast::parse_spectec_stream_parallel(super::WASM_AST_STR).unwrap();
+        assert!(parallel == super::get_wasm_spectec_ast());
+    }
+
+    #[test]
+    fn test_parse_file_matches_embedded_ast() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/wasm-3.0.spectec-ast");
+        // SAFETY: Nothing modifies the checked-in file while the tests run
+        let parsed = unsafe { spectec_ast::parse_spectec_file(path) }.unwrap();
+        assert!(parsed == super::get_wasm_spectec_ast());
+    }
+
+    #[test]
+    fn test_binary_encoding_reproduces_ast() {
+        let items = sexpr_parse::parse_sexpr_stream(super::WASM_AST_STR).unwrap();
+        let encoded = sexpr_parse::encode_sexpr_binary(&items);
+        assert!(encoded.len() < super::WASM_AST_STR.len() / 2);
+        assert!(sexpr_parse::decode_sexpr_binary(&encoded).unwrap() == items);
+        let decoded = spectec_ast::parse_spectec_binary(&encoded).unwrap();
+        assert!(decoded == super::get_wasm_spectec_ast());
+    }
+
+    #[test]
+    fn test_encoding_reproduces_ast() {
+        let ast = super::get_wasm_spectec_ast();
+        // Numbers written in hex are encoded in decimal, so the items aren't identical to the
+        // original, but they decode to the same AST
+        let items = decode::Encode::to_sexpr_items(&ast);
+        let written = sexpr_parse::PrettyOptions::default().format_stream(&items);
+        assert!(spectec_ast::parse_spectec_stream(&written).unwrap() == ast);
+    }
+
+    #[test]
+    fn test_pretty_print_reproduces_ast() {
+        let items = sexpr_parse::parse_sexpr_stream(super::WASM_AST_STR).unwrap();
+        let printed = sexpr_parse::PrettyOptions::default().format_stream(&items);
+        assert!(
+            printed == super::WASM_AST_STR,
+            "pretty-printed AST differs from the original"
+        );
+    }
 }